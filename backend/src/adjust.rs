@@ -0,0 +1,352 @@
+use crate::errors::ApiError;
+use crate::expr::Expression;
+use crate::models::{CountryData, DataPoint};
+use crate::query::point_year;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Official exchange rate, local currency units per US$, period average.
+pub const EXCHANGE_RATE: &str = "PA.NUS.FCRF";
+
+/// Consumer price index (2010 = 100).
+const CPI: &str = "FP.CPI.TOTL";
+
+/// GDP deflator (base year varies by country).
+const GDP_DEFLATOR: &str = "NY.GDP.DEFL.ZS";
+
+/// Countries, or aggregates, whose official exchange rate is used for a
+/// currency code.
+const CURRENCY_COUNTRIES: &[(&str, &str)] = &[
+    ("AUD", "AUS"),
+    ("BRL", "BRA"),
+    ("CAD", "CAN"),
+    ("CHF", "CHE"),
+    ("CNY", "CHN"),
+    ("DKK", "DNK"),
+    ("EUR", "EMU"),
+    ("GBP", "GBR"),
+    ("IDR", "IDN"),
+    ("INR", "IND"),
+    ("JPY", "JPN"),
+    ("KRW", "KOR"),
+    ("MXN", "MEX"),
+    ("NOK", "NOR"),
+    ("NZD", "NZL"),
+    ("RUB", "RUS"),
+    ("SEK", "SWE"),
+    ("TRY", "TUR"),
+    ("ZAR", "ZAF"),
+];
+
+/// Query parameters that restate a current US$ series in real terms and/or
+/// another currency.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AdjustmentQuery {
+    /// Base year for constant prices.
+    pub real: Option<i32>,
+    /// `cpi` (default), `gdp` or an indicator id.
+    pub deflator: Option<String>,
+    /// Country whose deflator is used; defaults to the currency's country.
+    pub deflator_country: Option<String>,
+    /// ISO 4217 code, `USD` (default), or `LCU` for each series' own currency.
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Currency {
+    Usd,
+    Local,
+    Of { code: String, country_id: String },
+}
+
+#[derive(Debug, Clone)]
+struct RealTerms {
+    base_year: i32,
+    deflator: String,
+    country_id: Option<String>,
+}
+
+/// A validated currency conversion and/or real-terms adjustment.
+#[derive(Debug, Clone)]
+pub struct Adjustment {
+    currency: Currency,
+    real: Option<RealTerms>,
+}
+
+impl AdjustmentQuery {
+    pub fn adjustment(&self) -> Result<Option<Adjustment>, ApiError> {
+        let currency = match self.currency.as_deref().map(str::to_uppercase) {
+            None => Currency::Usd,
+            Some(code) if code == "USD" => Currency::Usd,
+            Some(code) if code == "LCU" => Currency::Local,
+            Some(code) => match CURRENCY_COUNTRIES.iter().find(|(c, _)| *c == code) {
+                Some((_, country_id)) => Currency::Of {
+                    code,
+                    country_id: country_id.to_string(),
+                },
+                None => {
+                    let known: Vec<&str> = CURRENCY_COUNTRIES.iter().map(|(c, _)| *c).collect();
+                    return Err(ApiError::InvalidQuery(format!(
+                        "unsupported currency {}; expected USD, LCU or one of {}",
+                        code,
+                        known.join(", ")
+                    )));
+                }
+            },
+        };
+
+        let real = match self.real {
+            Some(base_year) => Some(RealTerms {
+                base_year,
+                deflator: match self.deflator.as_deref() {
+                    None => CPI.to_string(),
+                    Some(d) if d.eq_ignore_ascii_case("cpi") => CPI.to_string(),
+                    Some(d) if d.eq_ignore_ascii_case("gdp") => GDP_DEFLATOR.to_string(),
+                    Some(d) => d.to_uppercase(),
+                },
+                country_id: self.deflator_country.as_ref().map(|c| c.to_uppercase()),
+            }),
+            None if self.deflator.is_some() || self.deflator_country.is_some() => {
+                return Err(ApiError::InvalidQuery(
+                    "deflator and deflator_country require real".to_string(),
+                ))
+            }
+            None => None,
+        };
+
+        if currency == Currency::Usd && real.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Adjustment { currency, real }))
+    }
+}
+
+impl Adjustment {
+    /// Country whose exchange rate converts a series of `country_id`, if any.
+    pub fn rate_country(&self, country_id: &str) -> Option<String> {
+        match &self.currency {
+            Currency::Usd => None,
+            Currency::Local => Some(country_id.to_string()),
+            Currency::Of { country_id, .. } => Some(country_id.clone()),
+        }
+    }
+
+    /// Country and indicator of the deflator for a series of `country_id`.
+    /// Prices are deflated in the target currency, so the deflator defaults
+    /// to that currency's country.
+    pub fn deflator(&self, country_id: &str) -> Option<(String, String)> {
+        let real = self.real.as_ref()?;
+        let deflator_country = real.country_id.clone().unwrap_or_else(|| {
+            self.rate_country(country_id)
+                .unwrap_or_else(|| "USA".to_string())
+        });
+        Some((deflator_country, real.deflator.clone()))
+    }
+
+    /// Exchange rate and deflator series, as `(country_id, indicator_id)`,
+    /// needed to adjust a series of `country_id`.
+    pub fn references(&self, country_id: &str) -> Vec<(String, String)> {
+        self.rate_country(country_id)
+            .map(|country_id| (country_id, EXCHANGE_RATE.to_string()))
+            .into_iter()
+            .chain(self.deflator(country_id))
+            .collect()
+    }
+
+    /// Converts, then deflates, a current US$ series. Years without an
+    /// exchange rate or deflator value are dropped.
+    pub fn apply(
+        &self,
+        mut data: CountryData,
+        rates: Option<&CountryData>,
+        deflator: Option<&CountryData>,
+    ) -> Result<CountryData, ApiError> {
+        if !is_current_usd(&data.indicator_name) {
+            return Err(ApiError::TransformFailed(format!(
+                "{} is not a current US$ series",
+                data.indicator_id
+            )));
+        }
+
+        let mut points = data.data;
+        if let Some(rates) = rates {
+            let rates = by_date(&rates.data);
+            points = points
+                .into_iter()
+                .filter_map(|point| {
+                    let rate = *rates.get(point.year.as_str())?;
+                    Some(DataPoint {
+                        value: point.value * rate,
+                        ..point
+                    })
+                })
+                .collect();
+        }
+
+        if let (Some(real), Some(deflator)) = (&self.real, deflator) {
+            let base = deflator
+                .data
+                .iter()
+                .find(|point| point_year(point) == Some(real.base_year))
+                .map(|point| point.value)
+                .filter(|value| *value != 0.0)
+                .ok_or_else(|| {
+                    ApiError::TransformFailed(format!(
+                        "{} has no value for base year {} in {}",
+                        real.deflator, real.base_year, deflator.country_id
+                    ))
+                })?;
+            let deflator = by_date(&deflator.data);
+            points = points
+                .into_iter()
+                .filter_map(|point| {
+                    let index = *deflator.get(point.year.as_str())?;
+                    if index == 0.0 {
+                        return None;
+                    }
+                    Some(DataPoint {
+                        value: point.value * base / index,
+                        ..point
+                    })
+                })
+                .collect();
+        }
+
+        if points.is_empty() {
+            return Err(ApiError::TransformFailed(
+                "no years with both data and adjustment factors".to_string(),
+            ));
+        }
+        data.data = points;
+        data.adjustment = Some(self.describe(&data.country_id));
+        Ok(data)
+    }
+
+    /// E.g. `EUR, constant 2015 prices (FP.CPI.TOTL, EMU)`.
+    fn describe(&self, country_id: &str) -> String {
+        let currency = match &self.currency {
+            Currency::Usd => "USD".to_string(),
+            Currency::Local => "LCU".to_string(),
+            Currency::Of { code, .. } => code.clone(),
+        };
+        match (&self.real, self.deflator(country_id)) {
+            (Some(real), Some((deflator_country, deflator))) => format!(
+                "{}, constant {} prices ({}, {})",
+                currency, real.base_year, deflator, deflator_country
+            ),
+            _ => format!("{}, current prices", currency),
+        }
+    }
+}
+
+/// Checks that an expression over `series` is in current US$: its current
+/// US$ terms must leave exactly one US$ factor and no other US$ unit may
+/// appear. `NY.GDP.MKTP.CD/SP.POP.TOTL` qualifies, a ratio such as
+/// `NE.EXP.GNFS.CD/NY.GDP.MKTP.CD` does not.
+pub fn check_expression_unit(
+    expression: &Expression,
+    series: &HashMap<String, CountryData>,
+) -> Result<(), ApiError> {
+    let mut power = 0;
+    for (id, term_power) in expression.indicator_powers() {
+        let name = series
+            .get(id)
+            .map_or("", |data| data.indicator_name.as_str());
+        if is_current_usd(name) {
+            power += term_power;
+        } else if name.contains("US$") {
+            power = i32::MIN;
+            break;
+        }
+    }
+    if power != 1 {
+        return Err(ApiError::TransformFailed(format!(
+            "{} is not in current US$",
+            expression
+        )));
+    }
+    Ok(())
+}
+
+/// Constant-dollar series also mention US$ but are already in real terms.
+fn is_current_usd(indicator_name: &str) -> bool {
+    indicator_name.contains("current US$")
+}
+
+fn by_date(points: &[DataPoint]) -> HashMap<&str, f64> {
+    points
+        .iter()
+        .map(|point| (point.year.as_str(), point.value))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(indicator_id: &str, indicator_name: &str) -> CountryData {
+        CountryData {
+            indicator_name: indicator_name.to_string(),
+            ..CountryData::test_series("USA", indicator_id, &[("2020", 1.0)])
+        }
+    }
+
+    fn components() -> HashMap<String, CountryData> {
+        [
+            ("NY.GDP.MKTP.CD", "GDP (current US$)"),
+            (
+                "NE.EXP.GNFS.CD",
+                "Exports of goods and services (current US$)",
+            ),
+            ("NY.GDP.MKTP.KD", "GDP (constant 2015 US$)"),
+            ("SP.POP.TOTL", "Population, total"),
+        ]
+        .into_iter()
+        .map(|(id, name)| (id.to_string(), series(id, name)))
+        .collect()
+    }
+
+    fn check(spec: &str) -> Result<(), ApiError> {
+        check_expression_unit(&spec.parse().unwrap(), &components())
+    }
+
+    #[test]
+    fn expression_with_one_current_usd_factor_is_adjustable() {
+        assert!(check("NY.GDP.MKTP.CD/SP.POP.TOTL").is_ok());
+        assert!(check("1000/SP.POP.TOTL*NY.GDP.MKTP.CD").is_ok());
+    }
+
+    #[test]
+    fn expression_where_us_dollars_cancel_is_rejected() {
+        assert!(check("NE.EXP.GNFS.CD/NY.GDP.MKTP.CD*100").is_err());
+        assert!(check("SP.POP.TOTL/NY.GDP.MKTP.CD").is_err());
+        assert!(check("NY.GDP.MKTP.CD*NE.EXP.GNFS.CD").is_err());
+    }
+
+    #[test]
+    fn constant_dollar_terms_are_rejected() {
+        assert!(check("NY.GDP.MKTP.KD/SP.POP.TOTL").is_err());
+        assert!(check("NY.GDP.MKTP.CD/NY.GDP.MKTP.KD*NY.GDP.MKTP.CD").is_err());
+    }
+
+    #[test]
+    fn only_current_usd_series_are_adjusted() {
+        let adjustment = AdjustmentQuery {
+            currency: Some("LCU".to_string()),
+            ..AdjustmentQuery::default()
+        }
+        .adjustment()
+        .unwrap()
+        .unwrap();
+        let rates = series(EXCHANGE_RATE, "Official exchange rate");
+
+        let constant = series("NY.GDP.MKTP.KD", "GDP (constant 2015 US$)");
+        assert!(matches!(
+            adjustment.apply(constant, Some(&rates), None),
+            Err(ApiError::TransformFailed(_))
+        ));
+
+        let current = series("NY.GDP.MKTP.CD", "GDP (current US$)");
+        assert!(adjustment.apply(current, Some(&rates), None).is_ok());
+    }
+}
@@ -1,118 +1,1064 @@
-use crate::models::{Country, CountryData, DataPoint};
+use crate::cache::{CacheConfig, CacheKey, CacheStats, CachedResponse, ResponseCache};
+use crate::catalog::{apply_metadata, parse_catalog, WDI_SOURCE};
+use crate::circuit_breaker::{CircuitBreaker, CircuitStatus};
+use crate::config::UpstreamConfig;
+use crate::data;
 use crate::errors::ApiError;
+use crate::fixtures::FixtureRecorder;
+use crate::geography;
+use crate::models::{
+    Aggregate, Classification, Country, CountryData, DataPoint, Geography, Indicator,
+    IndicatorCatalog, IndicatorCategory,
+};
+use crate::query::SeriesQuery;
+use crate::retry::RetryPolicy;
+use crate::source::{BatchEntry, DataSource};
+use crate::store::{Store, Stored};
+use crate::validation::resolve_indicator;
+use crate::worldbank::{
+    decode_metadata, decode_page, CountryRecord, Metatype, ObservationRecord, Page, Reference,
+    AGGREGATE_REGION, INDICATOR_NOT_FOUND, INVALID_VALUE,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use log::warn;
 use reqwest::Client;
-use serde_json::Value;
-use std::sync::Arc;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-const WB_API: &str = "https://api.worldbank.org/v2";
+const PER_PAGE: u64 = 1000;
+const MAX_CONCURRENT_PAGES: usize = 4;
+/// Countries joined into one `country/A;B;C/...` request, keeping URLs short.
+const MAX_BATCH_COUNTRIES: usize = 50;
+/// How long the catalog served after a failed download is reused before the
+/// download is tried again.
+const CATALOG_RETRY_INTERVAL: Duration = Duration::from_secs(60);
 
 pub struct ApiClient {
     client: Client,
+    base_url: String,
+    cache: ResponseCache,
+    store: Option<Store>,
+    store_max_age: Duration,
+    recorder: Option<FixtureRecorder>,
+    retry: RetryPolicy,
+    breaker: CircuitBreaker,
+    catalog_load: tokio::sync::Mutex<()>,
+    catalog_fallback: Mutex<Option<(Instant, Arc<IndicatorCatalog>)>>,
 }
 
 impl ApiClient {
-    pub fn new() -> Self {
-        ApiClient {
-            client: Client::new(),
-        }
-    }
-
-    pub async fn get_countries(&self) -> Result<Vec<Country>, ApiError> {
-        let url = format!("{}/country?format=json&per_page=500", WB_API);
-        let response = self.client.get(&url).send().await?;
-        let data: Value = response.json().await?;
-
-        let countries = data
-            .get(1)
-            .and_then(|v| v.as_array())
-            .unwrap_or(&vec![])
-            .iter()
-            .filter_map(|c| {
-                let capital = c.get("capitalCity")?.as_str()?;
-                if capital.is_empty() {
-                    return None;
+    pub fn new(upstream: &UpstreamConfig, cache_config: CacheConfig) -> Result<Self, ApiError> {
+        let client = Client::builder()
+            .timeout(upstream.timeout())
+            .connect_timeout(upstream.connect_timeout())
+            .build()?;
+
+        Ok(ApiClient {
+            client,
+            base_url: upstream.base_url.trim_end_matches('/').to_string(),
+            cache: ResponseCache::new(cache_config),
+            store: None,
+            store_max_age: Duration::ZERO,
+            recorder: None,
+            retry: RetryPolicy::from_config(upstream),
+            breaker: CircuitBreaker::new(
+                upstream.circuit_failure_threshold,
+                upstream.circuit_open_duration(),
+            ),
+            catalog_load: tokio::sync::Mutex::new(()),
+            catalog_fallback: Mutex::new(None),
+        })
+    }
+
+    /// Reads through a persistent store, refetching entries older than
+    /// `max_age` and falling back to stored data when upstream fails.
+    pub fn with_store(mut self, store: Store, max_age: Duration) -> Self {
+        self.store = Some(store);
+        self.store_max_age = max_age;
+        self
+    }
+
+    /// Writes every raw upstream response into a fixtures directory so it can
+    /// later be replayed by `FixtureSource`.
+    pub fn with_recorder(mut self, recorder: FixtureRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache.stats()
+    }
+
+    pub fn circuit_status(&self) -> CircuitStatus {
+        self.breaker.status()
+    }
+
+    pub async fn get_geography(&self) -> Result<Geography, ApiError> {
+        let key = CacheKey::countries();
+        if let Some(CachedResponse::Geography(geography)) = self.cache.get(&key) {
+            return Ok(geography);
+        }
+
+        let stored = match &self.store {
+            Some(store) => store.load_geography().await.unwrap_or_else(|e| {
+                warn!("Failed to read countries from store: {}", e);
+                None
+            }),
+            None => None,
+        };
+
+        if let Some(stored) = &stored {
+            if self.is_fresh(stored.refreshed_at) {
+                self.cache.insert(key, CachedResponse::Geography(stored.value.clone()));
+                return Ok(stored.value.clone());
+            }
+        }
+
+        let geography = match self.fetch_geography().await {
+            Ok(geography) => geography,
+            Err(e) if is_upstream_failure(&e) => {
+                if let Some(CachedResponse::Geography(geography)) = self.cache.get_stale(&key) {
+                    warn!("Serving stale cached countries after upstream failure: {}", e);
+                    return Ok(geography);
+                }
+                match stored {
+                    Some(stored) => {
+                        warn!("Serving stored countries after upstream failure: {}", e);
+                        return Ok(stored.value);
+                    }
+                    None => return Err(e),
                 }
+            }
+            Err(e) => return Err(e),
+        };
 
-                let id = c.get("id")?.as_str()?.to_string();
-                let name = c.get("name")?.as_str()?.to_string();
-                let region = c
-                    .get("region")
-                    .and_then(|r| r.get("value"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("Unknown")
-                    .to_string();
-                let capital = capital.to_string();
-
-                Some(Country {
-                    id: id.clone(),
-                    name,
-                    region,
-                    capital,
-                    code: id,
-                })
-            })
-            .collect();
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save_geography(&geography).await {
+                warn!("Failed to persist countries: {}", e);
+            }
+        }
 
-        Ok(countries)
+        self.cache.insert(key, CachedResponse::Geography(geography.clone()));
+        Ok(geography)
     }
 
+    /// Returns the World Development Indicators catalog, read through the
+    /// cache and store like the country list. When no catalog can be loaded at
+    /// all, the curated indicators are served instead. Concurrent misses share
+    /// one download, and after a failed download its fallback is reused for
+    /// `CATALOG_RETRY_INTERVAL`.
+    pub async fn get_catalog(&self) -> Arc<IndicatorCatalog> {
+        let key = CacheKey::catalog();
+        if let Some(catalog) = self.cached_catalog(&key) {
+            return catalog;
+        }
+
+        let _loading = self.catalog_load.lock().await;
+        if let Some(catalog) = self.cached_catalog(&key) {
+            return catalog;
+        }
+
+        let stored = match &self.store {
+            Some(store) => store.load_catalog().await.unwrap_or_else(|e| {
+                warn!("Failed to read indicator catalog from store: {}", e);
+                None
+            }),
+            None => None,
+        };
+
+        if let Some(stored) = &stored {
+            if self.is_fresh(stored.refreshed_at) {
+                let catalog = Arc::new(stored.value.clone());
+                self.cache.insert(key, CachedResponse::Catalog(catalog.clone()));
+                return catalog;
+            }
+        }
+
+        let catalog = match self.fetch_catalog().await {
+            Ok(catalog) => catalog,
+            Err(e) => {
+                let fallback = if let Some(CachedResponse::Catalog(catalog)) =
+                    self.cache.get_stale(&key)
+                {
+                    warn!("Serving stale cached indicator catalog: {}", e);
+                    catalog
+                } else if let Some(stored) = stored {
+                    warn!("Serving stored indicator catalog: {}", e);
+                    Arc::new(stored.value)
+                } else {
+                    warn!("Indicator catalog unavailable, serving curated indicators: {}", e);
+                    Arc::new(IndicatorCatalog::featured_only(data::get_indicators()))
+                };
+                *self.catalog_fallback.lock().unwrap() = Some((Instant::now(), fallback.clone()));
+                return fallback;
+            }
+        };
+
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save_catalog(&catalog).await {
+                warn!("Failed to persist indicator catalog: {}", e);
+            }
+        }
+
+        let catalog = Arc::new(catalog);
+        self.cache.insert(key, CachedResponse::Catalog(catalog.clone()));
+        *self.catalog_fallback.lock().unwrap() = None;
+        catalog
+    }
+
+    /// The cached catalog, or the fallback of a recently failed download.
+    fn cached_catalog(&self, key: &CacheKey) -> Option<Arc<IndicatorCatalog>> {
+        if let Some(CachedResponse::Catalog(catalog)) = self.cache.get(key) {
+            return Some(catalog);
+        }
+        match &*self.catalog_fallback.lock().unwrap() {
+            Some((failed_at, catalog)) if failed_at.elapsed() < CATALOG_RETRY_INTERVAL => {
+                Some(catalog.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a catalog indicator completed with its definition, periodicity
+    /// and aggregation method from the WDI series metadata endpoint. If the
+    /// metadata cannot be fetched the catalog entry is returned as is.
+    pub async fn get_indicator_metadata(&self, indicator_id: &str) -> Result<Indicator, ApiError> {
+        let catalog = self.get_catalog().await;
+        let indicator = resolve_indicator(&catalog, indicator_id)?.into_owned();
+
+        let key = CacheKey::metadata(&indicator.id);
+        if let Some(CachedResponse::Indicator(indicator)) = self.cache.get(&key) {
+            return Ok(indicator);
+        }
+
+        match self.fetch_metadata(&indicator.id).await {
+            Ok(metatypes) => {
+                let indicator = apply_metadata(indicator, metatypes);
+                self.cache.insert(key, CachedResponse::Indicator(indicator.clone()));
+                Ok(indicator)
+            }
+            Err(e) => {
+                if let Some(CachedResponse::Indicator(indicator)) = self.cache.get_stale(&key) {
+                    warn!("Serving stale metadata for {}: {}", indicator_id, e);
+                    return Ok(indicator);
+                }
+                warn!("Metadata unavailable for {}: {}", indicator_id, e);
+                Ok(indicator)
+            }
+        }
+    }
+
+    /// Returns a series narrowed by `query`. Queries are answered from a full
+    /// series already held in the cache or store when possible; otherwise, and
+    /// always for `mrv`, they are forwarded to the World Bank as
+    /// `date`/`mrv`/`mrnev` options.
     pub async fn get_country_data(
         &self,
         country_id: &str,
         indicator_id: &str,
+        query: &SeriesQuery,
     ) -> Result<CountryData, ApiError> {
-        let url = format!(
-            "{}/country/{}/indicator/{}?format=json&per_page=500",
-            WB_API, country_id, indicator_id
+        query.validate()?;
+        if query.is_empty() {
+            return self.get_full_series(country_id, indicator_id).await;
+        }
+
+        let full_key = CacheKey::series(country_id, indicator_id);
+        if query.is_local() {
+            if let Some(CachedResponse::Series(data)) = self.cache.get(&full_key) {
+                return query.apply(data);
+            }
+        }
+
+        let stored = self.load_stored_series(country_id, indicator_id).await;
+        if let Some(stored) = &stored {
+            if query.is_local() && self.is_fresh(stored.refreshed_at) {
+                return query.apply(with_refreshed_at(stored));
+            }
+        }
+
+        let key = CacheKey::series_query(country_id, indicator_id, query);
+        if let Some(CachedResponse::Series(data)) = self.cache.get(&key) {
+            return Ok(data);
+        }
+
+        let params = query.to_params();
+        let data = match self.fetch_country_data(country_id, indicator_id, &params).await {
+            Ok(data) => query.apply(data)?,
+            Err(e) if is_upstream_failure(&e) => {
+                return match self.stale_series(&full_key, stored) {
+                    Some(data) => {
+                        warn!("Serving stale series after upstream failure: {}", e);
+                        query.apply(data)
+                    }
+                    None => Err(e),
+                };
+            }
+            Err(e) => return Err(e),
+        };
+
+        self.cache.insert(key, CachedResponse::Series(data.clone()));
+        Ok(data)
+    }
+
+    async fn get_full_series(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+    ) -> Result<CountryData, ApiError> {
+        let key = CacheKey::series(country_id, indicator_id);
+        if let Some(CachedResponse::Series(data)) = self.cache.get(&key) {
+            return Ok(data);
+        }
+
+        let stored = self.load_stored_series(country_id, indicator_id).await;
+        if let Some(stored) = &stored {
+            if self.is_fresh(stored.refreshed_at) {
+                let data = with_refreshed_at(stored);
+                self.cache.insert(key, CachedResponse::Series(data.clone()));
+                return Ok(data);
+            }
+        }
+
+        let mut data = match self.fetch_country_data(country_id, indicator_id, &[]).await {
+            Ok(data) => data,
+            Err(e) if is_upstream_failure(&e) => {
+                return match self.stale_series(&key, stored) {
+                    Some(data) => {
+                        warn!("Serving stale series after upstream failure: {}", e);
+                        Ok(data)
+                    }
+                    None => Err(e),
+                };
+            }
+            Err(e) => return Err(e),
+        };
+
+        data.refreshed_at = Some(Utc::now());
+        if let Some(store) = &self.store {
+            if let Err(e) = store.save_series(&data).await {
+                warn!("Failed to persist series: {}", e);
+            }
+        }
+
+        self.cache.insert(key, CachedResponse::Series(data.clone()));
+        Ok(data)
+    }
+
+    /// Returns one indicator for several countries. Series already held in the
+    /// cache or a fresh store entry are served locally; the rest are requested
+    /// together using the World Bank `country/A;B;C/indicator/X` syntax.
+    pub async fn get_batch_series(
+        &self,
+        country_ids: &[String],
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Vec<BatchEntry> {
+        if let Err(e) = query.validate() {
+            return country_ids
+                .iter()
+                .map(|country_id| (country_id.clone(), Err(e.clone())))
+                .collect();
+        }
+
+        let mut results = Vec::with_capacity(country_ids.len());
+        let mut missing = Vec::new();
+        for country_id in country_ids {
+            match self.local_series(country_id, indicator_id, query).await {
+                Some(result) => results.push((country_id.clone(), result)),
+                None => missing.push(country_id.clone()),
+            }
+        }
+
+        // Fixtures are recorded per country, so record mode fetches one by one.
+        if self.recorder.is_some() {
+            for country_id in missing {
+                let result = self.get_country_data(&country_id, indicator_id, query).await;
+                results.push((country_id, result));
+            }
+        } else {
+            for chunk in missing.chunks(MAX_BATCH_COUNTRIES) {
+                results.extend(self.fetch_batch(chunk, indicator_id, query).await);
+            }
+        }
+
+        results.sort_by_key(|(id, _)| country_ids.iter().position(|c| c == id));
+        results
+    }
+
+    /// One indicator for every economy and aggregate in a single
+    /// `country/all/indicator/X` request.
+    pub async fn get_cross_section(
+        &self,
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Result<Vec<CountryData>, ApiError> {
+        query.validate()?;
+        let key = CacheKey::cross_section(indicator_id, query);
+        if let Some(CachedResponse::CrossSection(series)) = self.cache.get(&key) {
+            return Ok(series);
+        }
+
+        let path = format!("country/all/indicator/{}", indicator_id);
+        let fetched = self
+            .fetch_all_pages(&path, &query.to_params())
+            .await
+            .and_then(|data| parse_cross_section(&data, indicator_id));
+
+        let series = match fetched {
+            Ok(series) => series,
+            Err(e) if is_upstream_failure(&e) => {
+                return match self.cache.get_stale(&key) {
+                    Some(CachedResponse::CrossSection(mut series)) => {
+                        warn!("Serving stale cross section after upstream failure: {}", e);
+                        for data in &mut series {
+                            data.stale = true;
+                        }
+                        Ok(series)
+                    }
+                    _ => Err(e),
+                };
+            }
+            Err(e) => return Err(series_error(e, "all", indicator_id)),
+        };
+
+        self.cache.insert(key, CachedResponse::CrossSection(series.clone()));
+        Ok(series)
+    }
+
+    /// A series answered from the cache or a fresh store entry, without going
+    /// upstream.
+    async fn local_series(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Option<Result<CountryData, ApiError>> {
+        if query.is_local() {
+            let full_key = CacheKey::series(country_id, indicator_id);
+            if let Some(CachedResponse::Series(data)) = self.cache.get(&full_key) {
+                return Some(query.apply(data));
+            }
+
+            if let Some(stored) = self.load_stored_series(country_id, indicator_id).await {
+                if self.is_fresh(stored.refreshed_at) {
+                    let data = with_refreshed_at(&stored);
+                    self.cache.insert(full_key, CachedResponse::Series(data.clone()));
+                    return Some(query.apply(data));
+                }
+            }
+        }
+
+        if query.is_empty() {
+            return None;
+        }
+        match self
+            .cache
+            .get(&CacheKey::series_query(country_id, indicator_id, query))
+        {
+            Some(CachedResponse::Series(data)) => Some(Ok(data)),
+            _ => None,
+        }
+    }
+
+    async fn fetch_batch(
+        &self,
+        country_ids: &[String],
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Vec<BatchEntry> {
+        let path = format!(
+            "country/{}/indicator/{}",
+            country_ids.join(";"),
+            indicator_id
         );
+        let fetched = self
+            .fetch_all_pages(&path, &query.to_params())
+            .await
+            .and_then(|data| parse_batch_series(&data, country_ids, indicator_id));
+
+        let series = match fetched {
+            Ok(series) => series,
+            Err(e) => {
+                let e = series_error(e, &country_ids.join(";"), indicator_id);
+                let mut results = Vec::with_capacity(country_ids.len());
+                for country_id in country_ids {
+                    results.push((
+                        country_id.clone(),
+                        self.stale_fallback(country_id, indicator_id, query, &e).await,
+                    ));
+                }
+                return results;
+            }
+        };
+
+        let mut results = Vec::with_capacity(series.len());
+        for (country_id, result) in series {
+            let result = match result {
+                Ok(mut data) if query.is_empty() => {
+                    data.refreshed_at = Some(Utc::now());
+                    if let Some(store) = &self.store {
+                        if let Err(e) = store.save_series(&data).await {
+                            warn!("Failed to persist series: {}", e);
+                        }
+                    }
+                    self.cache.insert(
+                        CacheKey::series(&country_id, indicator_id),
+                        CachedResponse::Series(data.clone()),
+                    );
+                    Ok(data)
+                }
+                Ok(data) => query.apply(data).inspect(|data| {
+                    self.cache.insert(
+                        CacheKey::series_query(&country_id, indicator_id, query),
+                        CachedResponse::Series(data.clone()),
+                    );
+                }),
+                Err(e) => Err(e),
+            };
+            results.push((country_id, result));
+        }
+        results
+    }
+
+    /// Stale data for one series of a failed batch, or the batch error itself.
+    async fn stale_fallback(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+        query: &SeriesQuery,
+        err: &ApiError,
+    ) -> Result<CountryData, ApiError> {
+        if !is_upstream_failure(err) {
+            return Err(err.clone());
+        }
+        let stored = self.load_stored_series(country_id, indicator_id).await;
+        match self.stale_series(&CacheKey::series(country_id, indicator_id), stored) {
+            Some(data) => {
+                warn!("Serving stale series after upstream failure: {}", err);
+                query.apply(data)
+            }
+            None => Err(err.clone()),
+        }
+    }
+
+    async fn load_stored_series(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+    ) -> Option<Stored<CountryData>> {
+        let store = self.store.as_ref()?;
+        store
+            .load_series(country_id, indicator_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Failed to read series from store: {}", e);
+                None
+            })
+    }
+
+    /// Older data to serve, flagged as stale, when upstream cannot be reached.
+    fn stale_series(
+        &self,
+        key: &CacheKey,
+        stored: Option<Stored<CountryData>>,
+    ) -> Option<CountryData> {
+        let mut data = match self.cache.get_stale(key) {
+            Some(CachedResponse::Series(data)) => data,
+            _ => with_refreshed_at(&stored?),
+        };
+        data.stale = true;
+        Some(data)
+    }
+
+    fn is_fresh(&self, refreshed_at: DateTime<Utc>) -> bool {
+        Utc::now()
+            .signed_duration_since(refreshed_at)
+            .to_std()
+            .map(|age| age < self.store_max_age)
+            .unwrap_or(true)
+    }
+
+    async fn fetch_geography(&self) -> Result<Geography, ApiError> {
+        let data = self.fetch_all_pages("country", &[]).await?;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_countries(&data).await;
+        }
+
+        parse_geography(&data)
+    }
+
+    async fn fetch_catalog(&self) -> Result<IndicatorCatalog, ApiError> {
+        let path = format!("source/{}/indicator", WDI_SOURCE);
+        let (indicators, topics) = futures::try_join!(
+            self.fetch_all_pages(&path, &[]),
+            self.fetch_all_pages("topic", &[]),
+        )?;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_catalog(&indicators, &topics).await;
+        }
+
+        parse_catalog(&indicators, &topics)
+    }
+
+    async fn fetch_metadata(&self, indicator_id: &str) -> Result<Vec<Metatype>, ApiError> {
+        let path = format!("sources/{}/series/{}/metadata", WDI_SOURCE, indicator_id);
+        let data = self.fetch_page(&path, &[], 1).await?;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_metadata(indicator_id, &data).await;
+        }
+
+        decode_metadata(&data)
+    }
+
+    async fn fetch_country_data(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+        params: &[(&str, String)],
+    ) -> Result<CountryData, ApiError> {
+        let path = format!("country/{}/indicator/{}", country_id, indicator_id);
+        let data = self
+            .fetch_all_pages(&path, params)
+            .await
+            .map_err(|e| series_error(e, country_id, indicator_id))?;
+
+        // Fixtures hold full series only, so narrowed responses are not recorded.
+        if let (Some(recorder), true) = (&self.recorder, params.is_empty()) {
+            recorder.record_series(country_id, indicator_id, &data).await;
+        }
+
+        parse_country_data(&data, country_id, indicator_id)
+    }
+
+    /// Fetches every page of a World Bank listing and merges them into a single
+    /// `[metadata, records]` envelope. The first page tells us how many pages
+    /// exist; the rest are requested concurrently.
+    async fn fetch_all_pages(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+    ) -> Result<Value, ApiError> {
+        let first_raw = self.fetch_page(path, params, 1).await?;
+        let first = decode_page::<Value>(&first_raw)?;
+        let total = first.meta.total;
+
+        let mut records = first.records;
+        if first.meta.pages > 1 {
+            let rest: Vec<Page<Value>> = stream::iter(2..=first.meta.pages)
+                .map(|page| async move {
+                    let raw = self.fetch_page(path, params, page).await?;
+                    decode_page::<Value>(&raw)
+                })
+                .buffered(MAX_CONCURRENT_PAGES)
+                .try_collect()
+                .await?;
+            for page in rest {
+                records.extend(page.records);
+            }
+        }
+
+        if records.len() as u64 != total {
+            warn!(
+                "World Bank reported {} records for {} but {} were returned",
+                total,
+                path,
+                records.len()
+            );
+        }
+
+        let mut meta = first_raw.get(0).cloned().unwrap_or_else(|| json!({}));
+        if let Some(meta) = meta.as_object_mut() {
+            meta.insert("page".to_string(), json!(1));
+            meta.insert("pages".to_string(), json!(1));
+            meta.insert("per_page".to_string(), json!(records.len()));
+        }
+
+        Ok(json!([meta, records]))
+    }
+
+    /// Fetches one page through the circuit breaker. Only failures that point
+    /// at an unhealthy upstream (after retries) count towards tripping it.
+    async fn fetch_page(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+        page: u64,
+    ) -> Result<Value, ApiError> {
+        let permit = self
+            .breaker
+            .allow_request()
+            .ok_or(ApiError::UpstreamUnavailable)?;
+
+        let result = self.fetch_page_with_retry(path, params, page).await;
+        match &result {
+            Err(e) if RetryPolicy::is_retryable(e) => permit.record_failure(),
+            _ => permit.record_success(),
+        }
+        result
+    }
+
+    async fn fetch_page_with_retry(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+        page: u64,
+    ) -> Result<Value, ApiError> {
+        let mut attempt = 0;
+        loop {
+            match self.try_fetch_page(path, params, page).await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.retry.max_retries && RetryPolicy::is_retryable(&e) => {
+                    let delay = self.retry.delay(attempt);
+                    warn!(
+                        "Request for {} page {} failed ({}), retrying in {:?}",
+                        path, page, e, delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn try_fetch_page(
+        &self,
+        path: &str,
+        params: &[(&str, String)],
+        page: u64,
+    ) -> Result<Value, ApiError> {
+        let url = format!("{}/{}", self.base_url, path);
+        let response = self
+            .client
+            .get(&url)
+            .query(&[("format", "json")])
+            .query(params)
+            .query(&[("per_page", PER_PAGE), ("page", page)])
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ApiError::UpstreamStatus(status.as_u16()));
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+/// Converts a World Bank `/country` response into countries and aggregates.
+/// Aggregates are the entries filed under the `NA` ("Aggregates") region.
+pub fn parse_geography(data: &Value) -> Result<Geography, ApiError> {
+    let page = decode_page::<CountryRecord>(data)?;
+
+    let (aggregates, countries): (Vec<_>, Vec<_>) = page
+        .records
+        .into_iter()
+        .partition(|c| c.region.id == AGGREGATE_REGION);
+
+    let countries: Vec<Country> = countries
+        .into_iter()
+        .map(|c| Country {
+            id: c.id,
+            iso2_code: c.iso2_code,
+            name: c.name,
+            region: c.region.value.trim().to_string(),
+            region_id: c.region.id,
+            admin_region: c
+                .admin_region
+                .filter(|region| !region.id.is_empty())
+                .map(classification),
+            income_level: classification(c.income_level),
+            lending_type: classification(c.lending_type),
+            capital: c.capital_city,
+            longitude: c.longitude.parse().ok(),
+            latitude: c.latitude.parse().ok(),
+        })
+        .collect();
+
+    let aggregates = aggregates
+        .into_iter()
+        .map(|a| {
+            let (kind, members) = geography::classify(&a.id, &countries);
+            Aggregate {
+                id: a.id,
+                iso2_code: a.iso2_code,
+                name: a.name,
+                kind,
+                members,
+            }
+        })
+        .collect();
+
+    Ok(Geography {
+        countries,
+        aggregates,
+    })
+}
+
+fn classification(reference: Reference) -> Classification {
+    Classification {
+        id: reference.id,
+        name: reference.value.trim().to_string(),
+    }
+}
+
+/// Converts a World Bank `/country/{id}/indicator/{id}` response into a series.
+pub fn parse_country_data(
+    data: &Value,
+    country_id: &str,
+    indicator_id: &str,
+) -> Result<CountryData, ApiError> {
+    let page = decode_page::<ObservationRecord>(data)
+        .map_err(|e| series_error(e, country_id, indicator_id))?;
+
+    series_from_records(page.records, country_id, indicator_id)
+}
+
+/// Splits a `/country/A;B;C/indicator/{id}` response into one series per
+/// requested country, in request order. Countries the response has no
+/// observations for get `ApiError::NoDataAvailable`; a country requested twice
+/// gets its series twice.
+pub fn parse_batch_series(
+    data: &Value,
+    country_ids: &[String],
+    indicator_id: &str,
+) -> Result<Vec<BatchEntry>, ApiError> {
+    let page = decode_page::<ObservationRecord>(data)?;
+
+    let mut by_country: HashMap<String, Vec<ObservationRecord>> = HashMap::new();
+    for record in page.records {
+        let key = if record.countryiso3code.is_empty() {
+            record.country.id.to_uppercase()
+        } else {
+            record.countryiso3code.to_uppercase()
+        };
+        by_country.entry(key).or_default().push(record);
+    }
+
+    Ok(country_ids
+        .iter()
+        .map(|country_id| {
+            let records = by_country
+                .get(&country_id.to_uppercase())
+                .cloned()
+                .unwrap_or_default();
+            let result = series_from_records(records, country_id, indicator_id);
+            (country_id.clone(), result)
+        })
+        .collect())
+}
+
+/// Splits a `/country/all/indicator/{id}` response into one series per
+/// economy or aggregate with observations, keyed by ISO3 code.
+pub fn parse_cross_section(data: &Value, indicator_id: &str) -> Result<Vec<CountryData>, ApiError> {
+    let page = decode_page::<ObservationRecord>(data)?;
 
-        let response = self.client.get(&url).send().await?;
-        let data: Value = response.json().await?;
-
-        let country_name = data
-            .get(1)
-            .and_then(|v| v.as_array())
-            .and_then(|arr| arr.first())
-            .and_then(|c| c.get("country"))
-            .and_then(|c| c.get("value"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("Unknown")
-            .to_string();
-
-        let indicator_name = data
-            .get(1)
-            .and_then(|v| v.as_array())
-            .and_then(|arr| arr.first())
-            .and_then(|c| c.get("indicator"))
-            .and_then(|c| c.get("value"))
-            .and_then(|v| v.as_str())
-            .unwrap_or("Unknown")
-            .to_string();
-
-        let mut data_points: Vec<DataPoint> = data
-            .get(1)
-            .and_then(|v| v.as_array())
-            .unwrap_or(&vec![])
-            .iter()
-            .filter_map(|point| {
-                let value = point.get("value")?.as_f64()?;
-                let year = point.get("date")?.as_str()?.to_string();
-                Some(DataPoint { year, value })
+    let mut by_country: HashMap<String, Vec<ObservationRecord>> = HashMap::new();
+    for record in page.records {
+        if record.countryiso3code.is_empty() {
+            continue;
+        }
+        by_country
+            .entry(record.countryiso3code.to_uppercase())
+            .or_default()
+            .push(record);
+    }
+
+    Ok(by_country
+        .into_iter()
+        .filter_map(|(country_id, records)| {
+            series_from_records(records, &country_id, indicator_id).ok()
+        })
+        .collect())
+}
+
+fn series_from_records(
+    records: Vec<ObservationRecord>,
+    country_id: &str,
+    indicator_id: &str,
+) -> Result<CountryData, ApiError> {
+    let (country_name, indicator_name) = match records.first() {
+        Some(first) => (first.country.value.clone(), first.indicator.value.clone()),
+        None => return Err(ApiError::NoDataAvailable),
+    };
+
+    let mut data_points: Vec<DataPoint> = records
+        .into_iter()
+        .filter_map(|point| {
+            Some(DataPoint {
+                year: point.date,
+                value: point.value?,
             })
-            .collect();
+        })
+        .collect();
 
-        data_points.sort_by(|a, b| a.year.cmp(&b.year));
+    data_points.sort_by(|a, b| a.year.cmp(&b.year));
 
-        if data_points.is_empty() {
-            return Err(ApiError::NoDataAvailable);
+    if data_points.is_empty() {
+        return Err(ApiError::NoDataAvailable);
+    }
+
+    Ok(CountryData {
+        country_id: country_id.to_string(),
+        country_name,
+        indicator_id: indicator_id.to_string(),
+        indicator_name,
+        data: data_points,
+        refreshed_at: None,
+        stale: false,
+        adjustment: None,
+        transform: None,
+        stats: None,
+    })
+}
+
+/// Maps World Bank rejections of a series request onto the id that caused them.
+fn series_error(err: ApiError, country_id: &str, indicator_id: &str) -> ApiError {
+    match err {
+        ApiError::UpstreamRejected { ref id, .. } if id == INVALID_VALUE => {
+            ApiError::CountryNotFound {
+                id: country_id.to_string(),
+                suggestions: Vec::new(),
+            }
         }
+        ApiError::UpstreamRejected { ref id, .. } if id == INDICATOR_NOT_FOUND => {
+            ApiError::IndicatorNotFound {
+                id: indicator_id.to_string(),
+                suggestions: Vec::new(),
+            }
+        }
+        err => err,
+    }
+}
+
+#[async_trait]
+impl DataSource for ApiClient {
+    async fn geography(&self) -> Result<Geography, ApiError> {
+        self.get_geography().await
+    }
+
+    async fn list_indicators(&self) -> Result<IndicatorCategory, ApiError> {
+        Ok(data::get_indicators())
+    }
+
+    async fn indicator_catalog(&self) -> Result<Arc<IndicatorCatalog>, ApiError> {
+        Ok(self.get_catalog().await)
+    }
+
+    async fn indicator_metadata(&self, indicator_id: &str) -> Result<Indicator, ApiError> {
+        self.get_indicator_metadata(indicator_id).await
+    }
 
-        Ok(CountryData {
-            country_id: country_id.to_string(),
-            country_name,
-            indicator_id: indicator_id.to_string(),
-            indicator_name,
-            data: data_points,
+    async fn fetch_series(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Result<CountryData, ApiError> {
+        self.get_country_data(country_id, indicator_id, query).await
+    }
+
+    async fn fetch_series_batch(
+        &self,
+        country_ids: &[String],
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Vec<BatchEntry> {
+        self.get_batch_series(country_ids, indicator_id, query).await
+    }
+
+    async fn fetch_cross_section(
+        &self,
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Result<Vec<CountryData>, ApiError> {
+        self.get_cross_section(indicator_id, query).await
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        Some(ApiClient::cache_stats(self))
+    }
+
+    fn circuit_status(&self) -> Option<CircuitStatus> {
+        Some(ApiClient::circuit_status(self))
+    }
+}
+
+/// Errors caused by upstream being slow or down, as opposed to the request
+/// itself being wrong. Only these fall back to stale data.
+fn is_upstream_failure(err: &ApiError) -> bool {
+    matches!(err, ApiError::UpstreamUnavailable) || RetryPolicy::is_retryable(err)
+}
+
+fn with_refreshed_at(stored: &Stored<CountryData>) -> CountryData {
+    let mut data = stored.value.clone();
+    data.refreshed_at = Some(stored.refreshed_at);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(country: &str, date: &str, value: Option<f64>) -> Value {
+        json!({
+            "indicator": {"id": "SP.POP.TOTL", "value": "Population, total"},
+            "country": {"id": &country[..2], "value": country},
+            "countryiso3code": country,
+            "date": date,
+            "value": value,
         })
     }
+
+    #[test]
+    fn batch_series_keeps_duplicate_countries() {
+        let data = json!([
+            {"page": 1, "pages": 1, "per_page": 50, "total": 2},
+            [observation("USA", "2020", Some(1.0)), observation("USA", "2021", Some(2.0))],
+        ]);
+        let ids = vec!["USA".to_string(), "USA".to_string(), "CHN".to_string()];
+        let series = parse_batch_series(&data, &ids, "SP.POP.TOTL").unwrap();
+
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].1.as_ref().unwrap().data.len(), 2);
+        assert_eq!(series[1].1.as_ref().unwrap().data.len(), 2);
+        assert!(matches!(series[2].1, Err(ApiError::NoDataAvailable)));
+    }
+
+    #[test]
+    fn batch_series_drops_empty_observations() {
+        let data = json!([
+            {"page": 1, "pages": 1, "per_page": 50, "total": 2},
+            [observation("USA", "2021", None), observation("USA", "2020", Some(1.0))],
+        ]);
+        let series = parse_batch_series(&data, &["usa".to_string()], "SP.POP.TOTL").unwrap();
+        let data = series[0].1.as_ref().unwrap();
+        assert_eq!(data.country_id, "usa");
+        assert_eq!(data.data.len(), 1);
+        assert_eq!(data.data[0].year, "2020");
+    }
+
+    #[actix_rt::test]
+    async fn failed_catalog_download_is_not_repeated() {
+        let upstream = UpstreamConfig {
+            base_url: "http://127.0.0.1:9".to_string(),
+            max_retries: 0,
+            ..UpstreamConfig::default()
+        };
+        let client = ApiClient::new(&upstream, CacheConfig::default()).unwrap();
+
+        let (first, second) = futures::join!(client.get_catalog(), client.get_catalog());
+        assert!(first.partial);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(Arc::ptr_eq(&first, &client.get_catalog().await));
+    }
 }
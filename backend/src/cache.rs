@@ -0,0 +1,191 @@
+use crate::models::{CountryData, Geography, Indicator, IndicatorCatalog};
+use crate::query::SeriesQuery;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub ttl: Duration,
+    pub max_entries: usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            ttl: Duration::from_secs(60 * 60),
+            max_entries: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    pub endpoint: &'static str,
+    pub country: Option<String>,
+    pub indicator: Option<String>,
+    pub query: Option<String>,
+}
+
+impl CacheKey {
+    pub fn countries() -> Self {
+        CacheKey {
+            endpoint: "country",
+            country: None,
+            indicator: None,
+            query: None,
+        }
+    }
+
+    pub fn catalog() -> Self {
+        CacheKey {
+            endpoint: "indicator",
+            country: None,
+            indicator: None,
+            query: None,
+        }
+    }
+
+    pub fn metadata(indicator_id: &str) -> Self {
+        CacheKey {
+            endpoint: "metadata",
+            country: None,
+            indicator: Some(indicator_id.to_uppercase()),
+            query: None,
+        }
+    }
+
+    pub fn series(country_id: &str, indicator_id: &str) -> Self {
+        CacheKey {
+            endpoint: "series",
+            country: Some(country_id.to_uppercase()),
+            indicator: Some(indicator_id.to_uppercase()),
+            query: None,
+        }
+    }
+
+    pub fn series_query(country_id: &str, indicator_id: &str, query: &SeriesQuery) -> Self {
+        CacheKey {
+            query: Some(query.cache_key()),
+            ..CacheKey::series(country_id, indicator_id)
+        }
+    }
+
+    pub fn cross_section(indicator_id: &str, query: &SeriesQuery) -> Self {
+        CacheKey {
+            endpoint: "cross_section",
+            country: None,
+            indicator: Some(indicator_id.to_uppercase()),
+            query: Some(query.cache_key()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CachedResponse {
+    Geography(Geography),
+    Catalog(Arc<IndicatorCatalog>),
+    Indicator(Indicator),
+    Series(CountryData),
+    CrossSection(Vec<CountryData>),
+}
+
+struct CacheEntry {
+    value: CachedResponse,
+    inserted_at: Instant,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    pub max_entries: usize,
+    pub ttl_secs: u64,
+}
+
+/// Shared response cache for World Bank lookups. Entries expire after the
+/// configured TTL; once `max_entries` is reached expired entries are dropped,
+/// then the oldest entry is evicted.
+pub struct ResponseCache {
+    config: CacheConfig,
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new(config: CacheConfig) -> Self {
+        ResponseCache {
+            config,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let entries = self.entries.lock().unwrap();
+
+        match entries.get(key) {
+            Some(entry) if entry.inserted_at.elapsed() < self.config.ttl => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                Some(entry.value.clone())
+            }
+            _ => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                None
+            }
+        }
+    }
+
+    /// Returns an entry even if its TTL has passed. Expired entries are kept
+    /// until evicted so they can be served when upstream is unavailable.
+    pub fn get_stale(&self, key: &CacheKey) -> Option<CachedResponse> {
+        let entries = self.entries.lock().unwrap();
+        entries.get(key).map(|entry| entry.value.clone())
+    }
+
+    pub fn insert(&self, key: CacheKey, value: CachedResponse) {
+        if self.config.max_entries == 0 {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+
+        if !entries.contains_key(&key) && entries.len() >= self.config.max_entries {
+            let ttl = self.config.ttl;
+            entries.retain(|_, entry| entry.inserted_at.elapsed() < ttl);
+
+            if entries.len() >= self.config.max_entries {
+                let oldest = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.inserted_at)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+
+        entries.insert(
+            key,
+            CacheEntry {
+                value,
+                inserted_at: Instant::now(),
+            },
+        );
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+            max_entries: self.config.max_entries,
+            ttl_secs: self.config.ttl.as_secs(),
+        }
+    }
+}
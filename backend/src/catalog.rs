@@ -0,0 +1,238 @@
+use crate::data;
+use crate::errors::ApiError;
+use crate::models::{Indicator, IndicatorCatalog, IndicatorCategory, Topic};
+use crate::worldbank::{decode_page, IndicatorRecord, Metatype, Reference};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// World Bank source id of the World Development Indicators.
+pub const WDI_SOURCE: &str = "2";
+
+/// Maps a World Bank topic id onto one of our indicator categories.
+pub fn topic_category(topic_id: &str) -> &'static str {
+    match topic_id {
+        // Agriculture, Energy & Mining, Environment, Climate Change
+        "1" | "5" | "6" | "19" => "environmental",
+        // Education, Health, Social Protection, Poverty, Social Development, Gender
+        "4" | "8" | "10" | "11" | "15" | "17" => "social",
+        // Urban Development
+        "16" => "demographic",
+        // Aid, Economy, Financial, Infrastructure, Private and Public Sector,
+        // Science & Technology, MDGs, External Debt, Trade
+        "2" | "3" | "7" | "9" | "12" | "13" | "14" | "18" | "20" | "21" => "economic",
+        _ => "other",
+    }
+}
+
+/// Builds the catalog from the World Bank `/source/2/indicator` and `/topic`
+/// responses. Curated indicators keep their own name, unit and category but
+/// pick up the upstream notes, sources and topics.
+pub fn parse_catalog(indicators: &Value, topics: &Value) -> Result<IndicatorCatalog, ApiError> {
+    let topics: Vec<Topic> = decode_page::<Reference>(topics)?
+        .records
+        .into_iter()
+        .map(|topic| Topic {
+            category: topic_category(&topic.id).to_string(),
+            id: topic.id,
+            name: topic.value.trim().to_string(),
+        })
+        .collect();
+
+    let records = decode_page::<IndicatorRecord>(indicators)?.records;
+    Ok(build_catalog(data::get_indicators(), topics, records))
+}
+
+fn build_catalog(
+    featured: IndicatorCategory,
+    topics: Vec<Topic>,
+    records: Vec<IndicatorRecord>,
+) -> IndicatorCatalog {
+    let mut curated: HashMap<&str, &Indicator> =
+        featured.iter().map(|i| (i.id.as_str(), i)).collect();
+
+    let mut indicators: Vec<Indicator> = records
+        .into_iter()
+        .map(|record| {
+            let topics = record
+                .topics
+                .iter()
+                .map(|topic| topic.value.trim().to_string())
+                .filter(|topic| !topic.is_empty())
+                .collect();
+            let source_database = record
+                .source
+                .as_ref()
+                .map(|source| source.value.clone())
+                .unwrap_or_default();
+            match curated.remove(record.id.as_str()) {
+                Some(indicator) => Indicator {
+                    source_note: record.source_note,
+                    source_organization: record.source_organization,
+                    source_database,
+                    topics,
+                    ..indicator.clone()
+                },
+                None => Indicator {
+                    category: record_category(&record).to_string(),
+                    unit: record_unit(&record),
+                    id: record.id,
+                    name: record.name,
+                    source_note: record.source_note,
+                    source_organization: record.source_organization,
+                    source_database,
+                    topics,
+                    ..Default::default()
+                },
+            }
+        })
+        .collect();
+    indicators.extend(curated.into_values().cloned());
+    indicators.sort_by(|a, b| a.id.cmp(&b.id));
+
+    IndicatorCatalog {
+        featured,
+        topics,
+        indicators,
+        partial: false,
+        search_index: OnceLock::new(),
+    }
+}
+
+/// `SP.*` series are population statistics, which the World Bank files under
+/// Health; otherwise the first topic that maps to a category wins.
+fn record_category(record: &IndicatorRecord) -> &'static str {
+    if record.id.starts_with("SP.") {
+        return "demographic";
+    }
+    record
+        .topics
+        .iter()
+        .map(|topic| topic_category(&topic.id))
+        .find(|category| *category != "other")
+        .unwrap_or("other")
+}
+
+/// The upstream unit is usually empty, in which case the unit in the
+/// trailing parentheses of the name is used, e.g. `GDP (current US$)`.
+fn record_unit(record: &IndicatorRecord) -> String {
+    if !record.unit.is_empty() {
+        return record.unit.clone();
+    }
+    record
+        .name
+        .strip_suffix(')')
+        .and_then(|name| name.rsplit_once('('))
+        .map(|(_, unit)| unit.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Fills in an indicator from its series metadata. Metatype ids vary in
+/// spacing and case between databases (`Aggregationmethod`, `Aggregation
+/// method`), so they are compared with only their letters.
+pub fn apply_metadata(mut indicator: Indicator, metatypes: Vec<Metatype>) -> Indicator {
+    for metatype in metatypes {
+        let value = metatype.value.trim().to_string();
+        if value.is_empty() {
+            continue;
+        }
+        let key: String = metatype
+            .id
+            .chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase();
+        let field = match key.as_str() {
+            "longdefinition" => &mut indicator.definition,
+            "shortdefinition" if indicator.definition.is_empty() => &mut indicator.definition,
+            "periodicity" => &mut indicator.periodicity,
+            "aggregationmethod" => &mut indicator.aggregation_method,
+            "source" if indicator.source_organization.is_empty() => {
+                &mut indicator.source_organization
+            }
+            "unitofmeasure" if indicator.unit.is_empty() => &mut indicator.unit,
+            _ => continue,
+        };
+        *field = value;
+    }
+    indicator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn page(records: Value) -> Value {
+        json!([{"page": 1, "pages": 1, "per_page": 1000, "total": 1}, records])
+    }
+
+    #[test]
+    fn curated_indicators_keep_their_name_and_pick_up_notes() {
+        let indicators = page(json!([
+            {
+                "id": "NY.GDP.MKTP.CD",
+                "name": "Gross domestic product (current US$)",
+                "sourceNote": "GDP at purchaser's prices.",
+                "topics": [{"id": "3", "value": "Economy & Growth "}, {}],
+            },
+            {
+                "id": "SP.URB.TOTL",
+                "name": "Urban population (% of total population)",
+                "topics": [{"id": "8", "value": "Health"}],
+            },
+        ]));
+        let catalog = parse_catalog(&indicators, &page(json!([]))).unwrap();
+        assert!(!catalog.partial);
+
+        let gdp = catalog.iter().find(|i| i.id == "NY.GDP.MKTP.CD").unwrap();
+        let curated = data::get_indicators();
+        let curated = curated.iter().find(|i| i.id == gdp.id).unwrap();
+        assert_eq!(gdp.name, curated.name);
+        assert_eq!(gdp.source_note, "GDP at purchaser's prices.");
+        assert_eq!(gdp.topics, ["Economy & Growth"]);
+
+        let urban = catalog.iter().find(|i| i.id == "SP.URB.TOTL").unwrap();
+        assert_eq!(urban.category, "demographic");
+        assert_eq!(urban.unit, "% of total population");
+
+        // Curated indicators missing upstream are still listed.
+        assert!(catalog.iter().any(|i| i.id == "SP.POP.TOTL"));
+    }
+
+    #[test]
+    fn uncurated_indicators_take_the_first_mapped_topic() {
+        let indicators = page(json!([{
+            "id": "EN.ATM.CO2E.KT",
+            "name": "CO2 emissions (kt)",
+            "unit": "kt of CO2",
+            "topics": [{"id": "99", "value": "Unknown"}, {"id": "19", "value": "Climate Change"}],
+        }]));
+        let catalog = parse_catalog(&indicators, &page(json!([]))).unwrap();
+        let co2 = catalog.iter().find(|i| i.id == "EN.ATM.CO2E.KT").unwrap();
+        assert_eq!(co2.category, "environmental");
+        assert_eq!(co2.unit, "kt of CO2");
+    }
+
+    #[test]
+    fn metadata_ids_ignore_spacing_and_case() {
+        let metatypes = vec![
+            Metatype {
+                id: "Aggregation method".to_string(),
+                value: "Weighted average".to_string(),
+            },
+            Metatype {
+                id: "Longdefinition".to_string(),
+                value: " Gross value added. ".to_string(),
+            },
+            Metatype {
+                id: "Periodicity".to_string(),
+                value: String::new(),
+            },
+        ];
+        let indicator = apply_metadata(Indicator::default(), metatypes);
+        assert_eq!(indicator.aggregation_method, "Weighted average");
+        assert_eq!(indicator.definition, "Gross value added.");
+        assert_eq!(indicator.periodicity, "");
+    }
+}
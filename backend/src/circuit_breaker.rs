@@ -0,0 +1,226 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CircuitStatus {
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+    pub failure_threshold: u32,
+    /// Seconds until an open circuit lets a probe request through.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_secs: Option<u64>,
+}
+
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_in_flight: bool,
+}
+
+/// Trips after `failure_threshold` consecutive upstream failures. While open
+/// every request fails fast; after `open_duration` a single probe is let
+/// through (half-open) and its outcome closes or re-opens the circuit. A probe
+/// dropped before reporting its outcome counts as a failure, so a cancelled
+/// request cannot leave the circuit half-open for good.
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold,
+            open_duration,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    /// A permit to send one request, or `None` while the circuit rejects
+    /// requests. The permit reports the request's outcome back.
+    pub fn allow_request(&self) -> Option<Permit<'_>> {
+        let mut inner = self.inner.lock().unwrap();
+        let probe = match inner.state {
+            CircuitState::Closed => false,
+            CircuitState::Open => {
+                let cooled_down = inner
+                    .opened_at
+                    .map(|opened_at| opened_at.elapsed() >= self.open_duration)
+                    .unwrap_or(true);
+                if !cooled_down {
+                    return None;
+                }
+                inner.state = CircuitState::HalfOpen;
+                true
+            }
+            CircuitState::HalfOpen if inner.probe_in_flight => return None,
+            CircuitState::HalfOpen => true,
+        };
+        if probe {
+            inner.probe_in_flight = true;
+        }
+        Some(Permit {
+            breaker: self,
+            probe,
+            finished: false,
+        })
+    }
+
+    fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        inner.probe_in_flight = false;
+    }
+
+    fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        inner.probe_in_flight = false;
+
+        if inner.state == CircuitState::HalfOpen
+            || inner.consecutive_failures >= self.failure_threshold
+        {
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    pub fn status(&self) -> CircuitStatus {
+        let inner = self.inner.lock().unwrap();
+        let retry_in_secs = match (inner.state, inner.opened_at) {
+            (CircuitState::Open, Some(opened_at)) => Some(
+                self.open_duration
+                    .saturating_sub(opened_at.elapsed())
+                    .as_secs(),
+            ),
+            _ => None,
+        };
+
+        CircuitStatus {
+            state: inner.state,
+            consecutive_failures: inner.consecutive_failures,
+            failure_threshold: self.failure_threshold,
+            retry_in_secs,
+        }
+    }
+}
+
+/// Outcome reporter for one request let through by [`CircuitBreaker`].
+pub struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    probe: bool,
+    finished: bool,
+}
+
+impl Permit<'_> {
+    pub fn record_success(mut self) {
+        self.finished = true;
+        self.breaker.record_success();
+    }
+
+    pub fn record_failure(mut self) {
+        self.finished = true;
+        self.breaker.record_failure();
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.probe && !self.finished {
+            self.breaker.record_failure();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(breaker: &CircuitBreaker) -> CircuitState {
+        breaker.status().state
+    }
+
+    fn fail(breaker: &CircuitBreaker) {
+        breaker.allow_request().unwrap().record_failure();
+    }
+
+    #[test]
+    fn opens_after_consecutive_failures() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        fail(&breaker);
+        assert_eq!(state(&breaker), CircuitState::Closed);
+        fail(&breaker);
+        assert_eq!(state(&breaker), CircuitState::Open);
+        assert!(breaker.allow_request().is_none());
+    }
+
+    #[test]
+    fn success_resets_failure_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+        fail(&breaker);
+        breaker.allow_request().unwrap().record_success();
+        fail(&breaker);
+        assert_eq!(state(&breaker), CircuitState::Closed);
+    }
+
+    #[test]
+    fn half_open_lets_one_probe_through() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        fail(&breaker);
+
+        let probe = breaker.allow_request().expect("probe after cooldown");
+        assert_eq!(state(&breaker), CircuitState::HalfOpen);
+        assert!(breaker.allow_request().is_none());
+
+        probe.record_success();
+        assert_eq!(state(&breaker), CircuitState::Closed);
+        assert!(breaker.allow_request().is_some());
+    }
+
+    #[test]
+    fn failed_probe_reopens() {
+        let breaker = CircuitBreaker::new(1, Duration::from_millis(20));
+        fail(&breaker);
+        std::thread::sleep(Duration::from_millis(30));
+
+        breaker.allow_request().unwrap().record_failure();
+        assert_eq!(state(&breaker), CircuitState::Open);
+        assert!(breaker.allow_request().is_none());
+    }
+
+    #[test]
+    fn dropped_probe_releases_half_open() {
+        let breaker = CircuitBreaker::new(1, Duration::ZERO);
+        fail(&breaker);
+
+        drop(breaker.allow_request().expect("probe after cooldown"));
+        assert_eq!(state(&breaker), CircuitState::Open);
+        assert!(breaker.allow_request().is_some());
+    }
+
+    #[test]
+    fn dropped_request_while_closed_is_ignored() {
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(60));
+        drop(breaker.allow_request());
+        assert_eq!(state(&breaker), CircuitState::Closed);
+        assert_eq!(breaker.status().consecutive_failures, 0);
+    }
+}
@@ -0,0 +1,325 @@
+use crate::cache::CacheConfig;
+use serde::Deserialize;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use thiserror::Error;
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {0}: {1}")]
+    Read(String, std::io::Error),
+
+    #[error("Failed to parse config file {0}: {1}")]
+    Parse(String, toml::de::Error),
+
+    #[error("Invalid value for {0}: {1}")]
+    InvalidEnv(String, String),
+
+    #[error("Invalid configuration: {0}")]
+    Invalid(String),
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub upstream: UpstreamConfig,
+    pub cache: CacheSettings,
+    pub store: StoreConfig,
+    pub source: SourceConfig,
+    pub compare: CompareConfig,
+    pub logging: LoggingConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UpstreamConfig {
+    pub base_url: String,
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    pub max_retries: u32,
+    pub retry_base_delay_ms: u64,
+    pub retry_max_delay_ms: u64,
+    pub circuit_failure_threshold: u32,
+    pub circuit_open_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheSettings {
+    pub ttl_secs: u64,
+    pub max_entries: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StoreConfig {
+    /// SQLite database path; an empty path disables the persistent store.
+    pub path: String,
+    pub max_age_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceKind {
+    Live,
+    Fixtures,
+    Record,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SourceConfig {
+    pub kind: SourceKind,
+    pub fixtures_dir: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CompareConfig {
+    /// Upper bound on series fetched in parallel for one `/api/compare` call.
+    pub max_concurrency: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    pub level: String,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            host: "0.0.0.0".to_string(),
+            port: 8080,
+        }
+    }
+}
+
+impl Default for UpstreamConfig {
+    fn default() -> Self {
+        UpstreamConfig {
+            base_url: "https://api.worldbank.org/v2".to_string(),
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+            max_retries: 3,
+            retry_base_delay_ms: 200,
+            retry_max_delay_ms: 5000,
+            circuit_failure_threshold: 5,
+            circuit_open_secs: 30,
+        }
+    }
+}
+
+impl Default for CacheSettings {
+    fn default() -> Self {
+        let defaults = CacheConfig::default();
+        CacheSettings {
+            ttl_secs: defaults.ttl.as_secs(),
+            max_entries: defaults.max_entries,
+        }
+    }
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        StoreConfig {
+            path: "bank.db".to_string(),
+            max_age_secs: 24 * 60 * 60,
+        }
+    }
+}
+
+impl Default for SourceConfig {
+    fn default() -> Self {
+        SourceConfig {
+            kind: SourceKind::Live,
+            fixtures_dir: PathBuf::from("fixtures"),
+        }
+    }
+}
+
+impl Default for CompareConfig {
+    fn default() -> Self {
+        CompareConfig { max_concurrency: 8 }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            level: "info".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Loads the configuration from the TOML file named by `BANK_CONFIG`
+    /// (falling back to `config.toml` when present), applies `BANK_*`
+    /// environment overrides and validates the result.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match env::var("BANK_CONFIG") {
+            Ok(path) => Self::from_file(&path)?,
+            Err(_) if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(DEFAULT_CONFIG_PATH)?
+            }
+            Err(_) => Config::default(),
+        };
+
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_string(), e))?;
+        toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_string(), e))
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        override_from_env("BANK_SERVER_HOST", &mut self.server.host)?;
+        override_from_env("BANK_SERVER_PORT", &mut self.server.port)?;
+        override_from_env("BANK_UPSTREAM_BASE_URL", &mut self.upstream.base_url)?;
+        override_from_env("BANK_UPSTREAM_TIMEOUT_SECS", &mut self.upstream.timeout_secs)?;
+        override_from_env(
+            "BANK_UPSTREAM_CONNECT_TIMEOUT_SECS",
+            &mut self.upstream.connect_timeout_secs,
+        )?;
+        override_from_env("BANK_UPSTREAM_MAX_RETRIES", &mut self.upstream.max_retries)?;
+        override_from_env(
+            "BANK_UPSTREAM_RETRY_BASE_DELAY_MS",
+            &mut self.upstream.retry_base_delay_ms,
+        )?;
+        override_from_env(
+            "BANK_UPSTREAM_RETRY_MAX_DELAY_MS",
+            &mut self.upstream.retry_max_delay_ms,
+        )?;
+        override_from_env(
+            "BANK_UPSTREAM_CIRCUIT_FAILURE_THRESHOLD",
+            &mut self.upstream.circuit_failure_threshold,
+        )?;
+        override_from_env(
+            "BANK_UPSTREAM_CIRCUIT_OPEN_SECS",
+            &mut self.upstream.circuit_open_secs,
+        )?;
+        override_from_env("BANK_CACHE_TTL_SECS", &mut self.cache.ttl_secs)?;
+        override_from_env("BANK_CACHE_MAX_ENTRIES", &mut self.cache.max_entries)?;
+        override_from_env("BANK_STORE_PATH", &mut self.store.path)?;
+        override_from_env("BANK_STORE_MAX_AGE_SECS", &mut self.store.max_age_secs)?;
+        override_from_env("BANK_FIXTURES_DIR", &mut self.source.fixtures_dir)?;
+        override_from_env("BANK_COMPARE_MAX_CONCURRENCY", &mut self.compare.max_concurrency)?;
+        override_from_env("BANK_LOG_LEVEL", &mut self.logging.level)?;
+
+        if let Ok(kind) = env::var("BANK_SOURCE") {
+            self.source.kind = match kind.as_str() {
+                "live" => SourceKind::Live,
+                "fixtures" => SourceKind::Fixtures,
+                "record" => SourceKind::Record,
+                _ => return Err(ConfigError::InvalidEnv("BANK_SOURCE".to_string(), kind)),
+            };
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.server.host.is_empty() {
+            return Err(ConfigError::Invalid("server.host must not be empty".to_string()));
+        }
+        if self.server.port == 0 {
+            return Err(ConfigError::Invalid("server.port must not be 0".to_string()));
+        }
+        if !self.upstream.base_url.starts_with("http://")
+            && !self.upstream.base_url.starts_with("https://")
+        {
+            return Err(ConfigError::Invalid(format!(
+                "upstream.base_url must be an http(s) URL, got {}",
+                self.upstream.base_url
+            )));
+        }
+        if self.upstream.timeout_secs == 0 || self.upstream.connect_timeout_secs == 0 {
+            return Err(ConfigError::Invalid(
+                "upstream timeouts must be greater than 0".to_string(),
+            ));
+        }
+        if self.upstream.retry_base_delay_ms > self.upstream.retry_max_delay_ms {
+            return Err(ConfigError::Invalid(
+                "upstream.retry_base_delay_ms must not exceed upstream.retry_max_delay_ms"
+                    .to_string(),
+            ));
+        }
+        if self.upstream.circuit_failure_threshold == 0 {
+            return Err(ConfigError::Invalid(
+                "upstream.circuit_failure_threshold must be greater than 0".to_string(),
+            ));
+        }
+        if self.cache.ttl_secs == 0 {
+            return Err(ConfigError::Invalid("cache.ttl_secs must be greater than 0".to_string()));
+        }
+        if self.compare.max_concurrency == 0 {
+            return Err(ConfigError::Invalid(
+                "compare.max_concurrency must be greater than 0".to_string(),
+            ));
+        }
+        if self.logging.level.parse::<log::LevelFilter>().is_err() {
+            return Err(ConfigError::Invalid(format!(
+                "logging.level must be one of off, error, warn, info, debug, trace, got {}",
+                self.logging.level
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn bind_address(&self) -> (String, u16) {
+        (self.server.host.clone(), self.server.port)
+    }
+}
+
+impl CacheSettings {
+    pub fn to_cache_config(&self) -> CacheConfig {
+        CacheConfig {
+            ttl: Duration::from_secs(self.ttl_secs),
+            max_entries: self.max_entries,
+        }
+    }
+}
+
+impl UpstreamConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn connect_timeout(&self) -> Duration {
+        Duration::from_secs(self.connect_timeout_secs)
+    }
+
+    pub fn circuit_open_duration(&self) -> Duration {
+        Duration::from_secs(self.circuit_open_secs)
+    }
+}
+
+impl StoreConfig {
+    pub fn max_age(&self) -> Duration {
+        Duration::from_secs(self.max_age_secs)
+    }
+}
+
+fn override_from_env<T: std::str::FromStr>(name: &str, target: &mut T) -> Result<(), ConfigError> {
+    if let Ok(value) = env::var(name) {
+        *target = value
+            .parse()
+            .map_err(|_| ConfigError::InvalidEnv(name.to_string(), value))?;
+    }
+    Ok(())
+}
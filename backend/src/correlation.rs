@@ -0,0 +1,283 @@
+use crate::errors::ApiError;
+use crate::models::{CountryData, Geography, Indicator};
+use crate::query::SeriesQuery;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Fewest observation pairs a coefficient is reported for; any two points
+/// correlate perfectly.
+const MIN_OBSERVATIONS: usize = 3;
+
+/// Query parameters of `/api/correlation`. With `country` the two indicators
+/// are correlated over time for that country, otherwise across economies.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CorrelationQuery {
+    pub x: String,
+    pub y: String,
+    pub country: Option<String>,
+    pub year: Option<i32>,
+    pub from: Option<i32>,
+    pub to: Option<i32>,
+}
+
+impl CorrelationQuery {
+    /// The window both series are fetched for. Cross-sectional correlations
+    /// need one, since every economy's full history would be fetched otherwise.
+    pub fn series_query(&self) -> Result<SeriesQuery, ApiError> {
+        let query = match self.year {
+            Some(_) if self.from.is_some() || self.to.is_some() => {
+                return Err(ApiError::InvalidQuery(
+                    "year cannot be combined with from or to".to_string(),
+                ))
+            }
+            Some(year) => SeriesQuery {
+                from: Some(year),
+                to: Some(year),
+                ..SeriesQuery::default()
+            },
+            None => SeriesQuery {
+                from: self.from,
+                to: self.to,
+                ..SeriesQuery::default()
+            },
+        };
+        if self.country.is_none() && query.is_empty() {
+            return Err(ApiError::InvalidQuery(
+                "cross-sectional correlation needs a year or a from/to range".to_string(),
+            ));
+        }
+        query.validate()?;
+        Ok(query)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrelationMode {
+    TimeSeries,
+    CrossSection,
+}
+
+/// One aligned pair: both indicators' values for the same period.
+#[derive(Debug, Clone, Serialize)]
+pub struct Observation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_name: Option<String>,
+    pub year: String,
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CorrelationResponse {
+    pub mode: CorrelationMode,
+    pub x_indicator_id: String,
+    pub x_indicator_name: String,
+    pub y_indicator_id: String,
+    pub y_indicator_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_id: Option<String>,
+    /// Number of aligned pairs.
+    pub n: usize,
+    /// Absent with fewer than three pairs or when either indicator is
+    /// constant.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pearson: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spearman: Option<f64>,
+    pub stale: bool,
+    pub observations: Vec<Observation>,
+}
+
+impl CorrelationResponse {
+    pub fn new(
+        x: &Indicator,
+        y: &Indicator,
+        country_id: Option<String>,
+        observations: Vec<Observation>,
+        stale: bool,
+    ) -> Result<Self, ApiError> {
+        if observations.is_empty() {
+            return Err(ApiError::NoDataAvailable);
+        }
+        let pairs: Vec<(f64, f64)> = observations.iter().map(|o| (o.x, o.y)).collect();
+        Ok(CorrelationResponse {
+            mode: match country_id {
+                Some(_) => CorrelationMode::TimeSeries,
+                None => CorrelationMode::CrossSection,
+            },
+            x_indicator_id: x.id.clone(),
+            x_indicator_name: x.name.clone(),
+            y_indicator_id: y.id.clone(),
+            y_indicator_name: y.name.clone(),
+            country_id,
+            n: pairs.len(),
+            pearson: pearson(&pairs),
+            spearman: spearman(&pairs),
+            stale,
+            observations,
+        })
+    }
+}
+
+/// Pairs the periods both series of one country have values for.
+pub fn time_series(x: &CountryData, y: &CountryData) -> Vec<Observation> {
+    align(x, y)
+        .into_iter()
+        .map(|(year, x, y)| Observation {
+            country_id: None,
+            country_name: None,
+            year,
+            x,
+            y,
+        })
+        .collect()
+}
+
+/// Pairs each economy's latest period with values for both indicators.
+/// Aggregates are left out.
+pub fn cross_section(
+    geography: &Geography,
+    x: &[CountryData],
+    y: &[CountryData],
+) -> Vec<Observation> {
+    let economies: HashSet<String> = geography
+        .countries
+        .iter()
+        .map(|country| country.id.to_uppercase())
+        .collect();
+    let y_by_country: HashMap<String, &CountryData> = y
+        .iter()
+        .map(|data| (data.country_id.to_uppercase(), data))
+        .collect();
+
+    let mut observations: Vec<Observation> = x
+        .iter()
+        .filter(|data| economies.contains(&data.country_id.to_uppercase()))
+        .filter_map(|x_data| {
+            let y_data = y_by_country.get(&x_data.country_id.to_uppercase())?;
+            let (year, x, y) = align(x_data, y_data).pop()?;
+            Some(Observation {
+                country_id: Some(x_data.country_id.clone()),
+                country_name: Some(x_data.country_name.clone()),
+                year,
+                x,
+                y,
+            })
+        })
+        .collect();
+    observations.sort_by(|a, b| a.country_id.cmp(&b.country_id));
+    observations
+}
+
+/// Periods present in both series, in order.
+fn align(x: &CountryData, y: &CountryData) -> Vec<(String, f64, f64)> {
+    let y_values: HashMap<&str, f64> = y
+        .data
+        .iter()
+        .map(|point| (point.year.as_str(), point.value))
+        .collect();
+    x.data
+        .iter()
+        .filter_map(|point| {
+            let y = y_values.get(point.year.as_str())?;
+            Some((point.year.clone(), point.value, *y))
+        })
+        .collect()
+}
+
+fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < MIN_OBSERVATIONS {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let sxx: f64 = pairs.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let syy: f64 = pairs.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    if sxx == 0.0 || syy == 0.0 {
+        return None;
+    }
+    let sxy: f64 = pairs.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    Some(sxy / (sxx * syy).sqrt())
+}
+
+/// Pearson correlation of the ranks, with ties given their average rank.
+fn spearman(pairs: &[(f64, f64)]) -> Option<f64> {
+    let xs: Vec<f64> = pairs.iter().map(|(x, _)| *x).collect();
+    let ys: Vec<f64> = pairs.iter().map(|(_, y)| *y).collect();
+    let ranked: Vec<(f64, f64)> = ranks(&xs).into_iter().zip(ranks(&ys)).collect();
+    pearson(&ranked)
+}
+
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|&a, &b| values[a].total_cmp(&values[b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start + 1;
+        while end < order.len() && values[order[end]] == values[order[start]] {
+            end += 1;
+        }
+        // Positions start..end share the average of ranks start+1..=end.
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &index in &order[start..end] {
+            ranks[index] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("coefficient");
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn tied_values_share_their_average_rank() {
+        assert_eq!(ranks(&[3.0, 1.0, 2.0, 2.0]), [4.0, 1.0, 2.5, 2.5]);
+        assert_eq!(ranks(&[7.0, 7.0, 7.0]), [2.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn spearman_is_one_for_monotonic_data_with_ties() {
+        let pairs = [(1.0, 10.0), (2.0, 100.0), (2.0, 100.0), (3.0, 1000.0)];
+        approx(spearman(&pairs), 1.0);
+        let pearson = pearson(&pairs).unwrap();
+        assert!(pearson < 1.0);
+    }
+
+    #[test]
+    fn spearman_with_ties_in_one_variable() {
+        // Ranks: x = [1, 2.5, 2.5, 4], y = [1, 2, 3, 4].
+        let pairs = [(1.0, 1.0), (2.0, 2.0), (2.0, 3.0), (3.0, 4.0)];
+        approx(spearman(&pairs), 4.5 / (4.5f64 * 5.0).sqrt());
+    }
+
+    #[test]
+    fn pearson_detects_negative_correlation() {
+        approx(pearson(&[(1.0, 6.0), (2.0, 4.0), (3.0, 2.0)]), -1.0);
+    }
+
+    #[test]
+    fn coefficients_need_three_pairs_and_variation() {
+        assert_eq!(pearson(&[(1.0, 2.0), (2.0, 4.0)]), None);
+        assert_eq!(pearson(&[(1.0, 2.0), (1.0, 4.0), (1.0, 5.0)]), None);
+        assert_eq!(spearman(&[(1.0, 2.0), (2.0, 2.0), (3.0, 2.0)]), None);
+    }
+}
@@ -1,25 +1,51 @@
 use actix_web::{error::ResponseError, http::StatusCode, HttpResponse};
 use serde_json::json;
-use std::fmt;
 use thiserror::Error;
 
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum ApiError {
-    #[error("Country not found: {0}")]
-    CountryNotFound(String),
+    #[error("Country not found: {id}{}", did_you_mean(.suggestions))]
+    CountryNotFound { id: String, suggestions: Vec<String> },
 
-    #[error("Indicator not found: {0}")]
-    IndicatorNotFound(String),
+    #[error("Indicator not found: {id}{}", did_you_mean(.suggestions))]
+    IndicatorNotFound { id: String, suggestions: Vec<String> },
 
     #[error("No data available")]
     NoDataAvailable,
 
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("Transform failed: {0}")]
+    TransformFailed(String),
+
     #[error("External API error: {0}")]
     ExternalApiError(String),
 
+    #[error("World Bank API responded with HTTP {0}")]
+    UpstreamStatus(u16),
+
+    #[error("World Bank API request timed out")]
+    UpstreamTimeout,
+
+    #[error("Could not reach World Bank API: {0}")]
+    UpstreamConnection(String),
+
+    #[error("World Bank API is temporarily unavailable")]
+    UpstreamUnavailable,
+
+    #[error("World Bank API rejected the request ({id}): {message}")]
+    UpstreamRejected { id: String, message: String },
+
     #[error("Serialization error: {0}")]
     SerializationError(String),
 
+    #[error("Fixture not found: {0}")]
+    FixtureNotFound(String),
+
+    #[error("Storage error: {0}")]
+    StorageError(String),
+
     #[error("Internal server error")]
     InternalError,
 }
@@ -27,31 +53,63 @@ pub enum ApiError {
 impl ResponseError for ApiError {
     fn status_code(&self) -> StatusCode {
         match self {
-            ApiError::CountryNotFound(_) | ApiError::IndicatorNotFound(_) => {
+            ApiError::CountryNotFound { .. } | ApiError::IndicatorNotFound { .. } => {
                 StatusCode::NOT_FOUND
             }
-            ApiError::NoDataAvailable => StatusCode::NOT_FOUND,
-            ApiError::ExternalApiError(_) | ApiError::SerializationError(_) => {
-                StatusCode::BAD_GATEWAY
+            ApiError::NoDataAvailable | ApiError::FixtureNotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::InvalidQuery(_) => StatusCode::BAD_REQUEST,
+            ApiError::TransformFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::ExternalApiError(_)
+            | ApiError::UpstreamConnection(_)
+            | ApiError::UpstreamStatus(_)
+            | ApiError::UpstreamRejected { .. }
+            | ApiError::SerializationError(_) => StatusCode::BAD_GATEWAY,
+            ApiError::UpstreamTimeout => StatusCode::GATEWAY_TIMEOUT,
+            ApiError::UpstreamUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::StorageError(_) | ApiError::InternalError => {
+                StatusCode::INTERNAL_SERVER_ERROR
             }
-            ApiError::InternalError => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse {
         let status = self.status_code();
-        let error_response = json!({
+        let mut error_response = json!({
             "error": status.to_string(),
             "message": self.to_string()
         });
 
+        if let ApiError::CountryNotFound { suggestions, .. }
+        | ApiError::IndicatorNotFound { suggestions, .. } = self
+        {
+            if !suggestions.is_empty() {
+                error_response["suggestions"] = json!(suggestions);
+            }
+        }
+
         HttpResponse::build(status).json(error_response)
     }
 }
 
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(" (did you mean {}?)", suggestions.join(", "))
+    }
+}
+
 impl From<reqwest::Error> for ApiError {
     fn from(err: reqwest::Error) -> Self {
-        ApiError::ExternalApiError(err.to_string())
+        if err.is_timeout() {
+            ApiError::UpstreamTimeout
+        } else if err.is_decode() {
+            ApiError::SerializationError(err.to_string())
+        } else if err.is_connect() || err.is_request() {
+            ApiError::UpstreamConnection(err.to_string())
+        } else {
+            ApiError::ExternalApiError(err.to_string())
+        }
     }
 }
 
@@ -60,3 +118,9 @@ impl From<serde_json::Error> for ApiError {
         ApiError::SerializationError(err.to_string())
     }
 }
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(err: rusqlite::Error) -> Self {
+        ApiError::StorageError(err.to_string())
+    }
+}
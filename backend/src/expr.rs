@@ -0,0 +1,341 @@
+use crate::errors::ApiError;
+use crate::models::{CountryData, DataPoint};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Upper bound on terms in one expression, which each cost an upstream fetch.
+const MAX_TERMS: usize = 8;
+
+/// The `expr=` query parameter of `/api/data/{country_id}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExpressionQuery {
+    pub expr: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Term {
+    Series(String),
+    Constant(f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Multiply,
+    Divide,
+}
+
+/// A derived series such as `NY.GDP.MKTP.CD/SP.POP.TOTL` or
+/// `GC.DOD.TOTL.CN/NY.GDP.MKTP.CN*100`: indicator ids and numeric constants
+/// joined by `*` and `/`, evaluated left to right for each year.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    first: Term,
+    rest: Vec<(Op, Term)>,
+}
+
+impl FromStr for Expression {
+    type Err = ApiError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut terms = Vec::new();
+        let mut ops = Vec::new();
+        let mut start = 0;
+        for (i, c) in spec.char_indices() {
+            let op = match c {
+                '*' => Op::Multiply,
+                '/' => Op::Divide,
+                _ => continue,
+            };
+            terms.push(parse_term(&spec[start..i])?);
+            ops.push(op);
+            start = i + 1;
+        }
+        terms.push(parse_term(&spec[start..])?);
+
+        if terms.len() > MAX_TERMS {
+            return Err(ApiError::InvalidQuery(format!(
+                "expressions may have at most {} terms",
+                MAX_TERMS
+            )));
+        }
+        if !terms.iter().any(|term| matches!(term, Term::Series(_))) {
+            return Err(ApiError::InvalidQuery(
+                "expression must reference at least one indicator".to_string(),
+            ));
+        }
+
+        let mut terms = terms.into_iter();
+        let first = terms.next().expect("at least one term is parsed");
+        Ok(Expression {
+            first,
+            rest: ops.into_iter().zip(terms).collect(),
+        })
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.first)?;
+        for (op, term) in &self.rest {
+            write!(f, "{}{}", op, term)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Series(id) => write!(f, "{}", id),
+            Term::Constant(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Multiply => write!(f, "*"),
+            Op::Divide => write!(f, "/"),
+        }
+    }
+}
+
+impl Expression {
+    /// True for a bare indicator id, which needs no evaluation.
+    pub fn as_indicator(&self) -> Option<&str> {
+        match (&self.first, self.rest.is_empty()) {
+            (Term::Series(id), true) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// Distinct indicator ids referenced, in order of appearance.
+    pub fn indicator_ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = Vec::new();
+        for term in self.terms() {
+            if let Term::Series(id) = term {
+                if !ids.contains(&id.as_str()) {
+                    ids.push(id);
+                }
+            }
+        }
+        ids
+    }
+
+    /// Each indicator term with the power it enters the result with: 1 when
+    /// multiplied, -1 when divided. Repeated ids appear once per term.
+    pub fn indicator_powers(&self) -> impl Iterator<Item = (&str, i32)> {
+        std::iter::once((Op::Multiply, &self.first))
+            .chain(self.rest.iter().map(|(op, term)| (*op, term)))
+            .filter_map(|(op, term)| match term {
+                Term::Series(id) => Some((id.as_str(), if op == Op::Divide { -1 } else { 1 })),
+                Term::Constant(_) => None,
+            })
+    }
+
+    /// Rewrites each indicator id, e.g. to its canonical catalog form.
+    pub fn resolve<F>(self, mut resolve: F) -> Result<Self, ApiError>
+    where
+        F: FnMut(&str) -> Result<String, ApiError>,
+    {
+        let mut resolve_term = |term: Term| match term {
+            Term::Series(id) => resolve(&id).map(Term::Series),
+            constant => Ok(constant),
+        };
+        Ok(Expression {
+            first: resolve_term(self.first)?,
+            rest: self
+                .rest
+                .into_iter()
+                .map(|(op, term)| resolve_term(term).map(|term| (op, term)))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Evaluates the expression for every period all referenced series have
+    /// a value for. Periods dividing by zero are skipped.
+    pub fn evaluate(&self, series: &HashMap<String, CountryData>) -> Result<CountryData, ApiError> {
+        let components: Vec<(&str, &CountryData)> = self
+            .indicator_ids()
+            .into_iter()
+            .map(|id| {
+                series
+                    .get(id)
+                    .map(|data| (id, data))
+                    .ok_or(ApiError::NoDataAvailable)
+            })
+            .collect::<Result<_, _>>()?;
+        let lookup: HashMap<&str, HashMap<&str, f64>> = components
+            .iter()
+            .map(|(id, data)| {
+                let values = data
+                    .data
+                    .iter()
+                    .map(|point| (point.year.as_str(), point.value))
+                    .collect();
+                (*id, values)
+            })
+            .collect();
+
+        let value_at = |term: &Term, year: &str| match term {
+            Term::Series(id) => lookup.get(id.as_str())?.get(year).copied(),
+            Term::Constant(value) => Some(*value),
+        };
+
+        let (_, base) = components[0];
+        let points: Vec<DataPoint> = base
+            .data
+            .iter()
+            .filter_map(|point| {
+                let year = point.year.as_str();
+                let mut value = value_at(&self.first, year)?;
+                for (op, term) in &self.rest {
+                    let operand = value_at(term, year)?;
+                    value = match op {
+                        Op::Multiply => value * operand,
+                        Op::Divide if operand == 0.0 => return None,
+                        Op::Divide => value / operand,
+                    };
+                }
+                Some(DataPoint {
+                    year: point.year.clone(),
+                    value,
+                })
+            })
+            .collect();
+
+        if points.is_empty() {
+            return Err(ApiError::NoDataAvailable);
+        }
+
+        let refreshed_at: Option<DateTime<Utc>> =
+            components.iter().filter_map(|(_, data)| data.refreshed_at).min();
+        Ok(CountryData {
+            country_id: base.country_id.clone(),
+            country_name: base.country_name.clone(),
+            indicator_id: self.to_string(),
+            indicator_name: self.name(&components),
+            data: points,
+            refreshed_at,
+            stale: components.iter().any(|(_, data)| data.stale),
+            adjustment: None,
+            transform: None,
+            stats: None,
+        })
+    }
+
+    fn name(&self, components: &[(&str, &CountryData)]) -> String {
+        let term_name = |term: &Term| match term {
+            Term::Series(id) => components
+                .iter()
+                .find(|(component, _)| component == id)
+                .map_or_else(|| id.clone(), |(_, data)| data.indicator_name.clone()),
+            Term::Constant(value) => value.to_string(),
+        };
+
+        let mut name = term_name(&self.first);
+        for (op, term) in &self.rest {
+            name = format!("{} {} {}", name, op, term_name(term));
+        }
+        name
+    }
+
+    fn terms(&self) -> impl Iterator<Item = &Term> {
+        std::iter::once(&self.first).chain(self.rest.iter().map(|(_, term)| term))
+    }
+}
+
+fn parse_term(text: &str) -> Result<Term, ApiError> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(ApiError::InvalidQuery(
+            "expression has an empty term".to_string(),
+        ));
+    }
+    match text.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(Term::Constant(value)),
+        _ => Ok(Term::Series(text.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_displays_terms_in_order() {
+        let expression: Expression = "GC.DOD.TOTL.CN / NY.GDP.MKTP.CN * 100".parse().unwrap();
+        assert_eq!(expression.to_string(), "GC.DOD.TOTL.CN/NY.GDP.MKTP.CN*100");
+        assert_eq!(
+            expression.indicator_ids(),
+            ["GC.DOD.TOTL.CN", "NY.GDP.MKTP.CN"]
+        );
+        assert_eq!(expression.as_indicator(), None);
+        assert_eq!(
+            "SP.POP.TOTL".parse::<Expression>().unwrap().as_indicator(),
+            Some("SP.POP.TOTL")
+        );
+    }
+
+    #[test]
+    fn rejects_empty_terms() {
+        for spec in [
+            "",
+            "NY.GDP.MKTP.CD//SP.POP.TOTL",
+            "NY.GDP.MKTP.CD*",
+            "/SP.POP.TOTL",
+        ] {
+            assert!(
+                matches!(spec.parse::<Expression>(), Err(ApiError::InvalidQuery(_))),
+                "{:?} should be rejected",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn limits_the_number_of_terms() {
+        let terms = |n: usize| vec!["SP.POP.TOTL"; n].join("*");
+        assert!(terms(MAX_TERMS).parse::<Expression>().is_ok());
+        assert!(matches!(
+            terms(MAX_TERMS + 1).parse::<Expression>(),
+            Err(ApiError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_constant_only_expressions() {
+        assert!(matches!(
+            "2*3".parse::<Expression>(),
+            Err(ApiError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn evaluates_common_years_and_skips_division_by_zero() {
+        let expression: Expression = "A/B*100".parse().unwrap();
+        let inputs = HashMap::from([
+            (
+                "A".to_string(),
+                CountryData::test_series(
+                    "USA",
+                    "A",
+                    &[("2019", 1.0), ("2020", 2.0), ("2021", 3.0)],
+                ),
+            ),
+            (
+                "B".to_string(),
+                CountryData::test_series("USA", "B", &[("2020", 4.0), ("2021", 0.0)]),
+            ),
+        ]);
+        let result = expression.evaluate(&inputs).unwrap();
+        assert_eq!(result.indicator_id, "A/B*100");
+        assert_eq!(result.data.len(), 1);
+        assert_eq!(result.data[0].year, "2020");
+        assert_eq!(result.data[0].value, 50.0);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::api::{parse_country_data, parse_geography};
+use crate::catalog::{apply_metadata, parse_catalog};
+use crate::data;
+use crate::errors::ApiError;
+use crate::models::{CountryData, Geography, Indicator, IndicatorCatalog, IndicatorCategory};
+use crate::query::SeriesQuery;
+use crate::source::DataSource;
+use crate::validation::find_indicator;
+use crate::worldbank::decode_metadata;
+use async_trait::async_trait;
+use log::{debug, warn};
+use serde_json::Value;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+fn countries_path(dir: &Path) -> PathBuf {
+    dir.join("countries.json")
+}
+
+fn indicators_path(dir: &Path) -> PathBuf {
+    dir.join("indicators.json")
+}
+
+fn topics_path(dir: &Path) -> PathBuf {
+    dir.join("topics.json")
+}
+
+fn metadata_path(dir: &Path, indicator_id: &str) -> PathBuf {
+    dir.join("metadata")
+        .join(format!("{}.json", indicator_id.to_uppercase()))
+}
+
+fn series_path(dir: &Path, country_id: &str, indicator_id: &str) -> PathBuf {
+    dir.join("series")
+        .join(country_id.to_uppercase())
+        .join(format!("{}.json", indicator_id.to_uppercase()))
+}
+
+/// Serves recorded World Bank responses from disk, for running the backend
+/// without network access. Each file holds the raw JSON body returned by the
+/// World Bank API:
+///
+/// ```text
+/// <dir>/countries.json
+/// <dir>/indicators.json
+/// <dir>/topics.json
+/// <dir>/metadata/<INDICATOR>.json
+/// <dir>/series/<COUNTRY>/<INDICATOR>.json
+/// ```
+///
+/// Without `indicators.json` and `topics.json` only the curated indicators
+/// are available, and indicators without a metadata file are described by
+/// their catalog entry alone.
+pub struct FixtureSource {
+    dir: PathBuf,
+}
+
+impl FixtureSource {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FixtureSource { dir: dir.into() }
+    }
+
+    async fn read(&self, path: &Path) -> Result<Value, ApiError> {
+        match tokio::fs::read_to_string(path).await {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                Err(ApiError::FixtureNotFound(path.display().to_string()))
+            }
+            Err(e) => Err(ApiError::StorageError(e.to_string())),
+        }
+    }
+}
+
+#[async_trait]
+impl DataSource for FixtureSource {
+    async fn geography(&self) -> Result<Geography, ApiError> {
+        let data = self.read(&countries_path(&self.dir)).await?;
+        parse_geography(&data)
+    }
+
+    async fn list_indicators(&self) -> Result<IndicatorCategory, ApiError> {
+        Ok(data::get_indicators())
+    }
+
+    async fn indicator_catalog(&self) -> Result<Arc<IndicatorCatalog>, ApiError> {
+        let indicators = match self.read(&indicators_path(&self.dir)).await {
+            Ok(indicators) => indicators,
+            Err(ApiError::FixtureNotFound(_)) => {
+                return Ok(Arc::new(IndicatorCatalog::featured_only(
+                    data::get_indicators(),
+                )))
+            }
+            Err(e) => return Err(e),
+        };
+        let topics = self.read(&topics_path(&self.dir)).await?;
+        Ok(Arc::new(parse_catalog(&indicators, &topics)?))
+    }
+
+    async fn indicator_metadata(&self, indicator_id: &str) -> Result<Indicator, ApiError> {
+        let catalog = self.indicator_catalog().await?;
+        let indicator = find_indicator(catalog.iter(), indicator_id)?.clone();
+        match self.read(&metadata_path(&self.dir, &indicator.id)).await {
+            Ok(data) => Ok(apply_metadata(indicator, decode_metadata(&data)?)),
+            Err(ApiError::FixtureNotFound(_)) => Ok(indicator),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn fetch_series(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Result<CountryData, ApiError> {
+        query.validate()?;
+        let data = self
+            .read(&series_path(&self.dir, country_id, indicator_id))
+            .await?;
+        query.apply(parse_country_data(&data, country_id, indicator_id)?)
+    }
+}
+
+/// Captures live World Bank responses into a fixtures directory readable by
+/// `FixtureSource`. Recording failures are logged and never fail a request.
+pub struct FixtureRecorder {
+    dir: PathBuf,
+}
+
+impl FixtureRecorder {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FixtureRecorder { dir: dir.into() }
+    }
+
+    pub async fn record_countries(&self, data: &Value) {
+        self.write(countries_path(&self.dir), data).await;
+    }
+
+    pub async fn record_catalog(&self, indicators: &Value, topics: &Value) {
+        self.write(indicators_path(&self.dir), indicators).await;
+        self.write(topics_path(&self.dir), topics).await;
+    }
+
+    pub async fn record_metadata(&self, indicator_id: &str, data: &Value) {
+        self.write(metadata_path(&self.dir, indicator_id), data).await;
+    }
+
+    pub async fn record_series(&self, country_id: &str, indicator_id: &str, data: &Value) {
+        self.write(series_path(&self.dir, country_id, indicator_id), data)
+            .await;
+    }
+
+    async fn write(&self, path: PathBuf, data: &Value) {
+        let result = async {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let contents = serde_json::to_string_pretty(data)?;
+            tokio::fs::write(&path, contents).await
+        }
+        .await;
+
+        match result {
+            Ok(()) => debug!("Recorded fixture {}", path.display()),
+            Err(e) => warn!("Failed to record fixture {}: {}", path.display(), e),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::models::{AggregateKind, Country};
+
+/// World Bank id of the all-countries aggregate.
+const WORLD: &str = "WLD";
+
+/// Income groups made up of other income groups.
+const COMPOSITE_INCOME_LEVELS: &[(&str, &[&str])] =
+    &[("MIC", &["LMC", "UMC"]), ("LMY", &["LIC", "LMC", "UMC"])];
+
+/// Reads the id of the grouping a country belongs to.
+type Grouping = fn(&Country) -> &str;
+
+/// Works out what kind of grouping an aggregate is, and its members, from the
+/// classifications of the countries. Groupings the country list says nothing
+/// about, such as the euro area or OECD members, have no member list.
+pub fn classify(id: &str, countries: &[Country]) -> (AggregateKind, Option<Vec<String>>) {
+    if id == WORLD {
+        return (AggregateKind::World, Some(member_ids(countries, |_| true)));
+    }
+
+    let groupings: [(AggregateKind, Grouping); 4] = [
+        (AggregateKind::Region, |c| &c.region_id),
+        (AggregateKind::AdminRegion, |c| {
+            c.admin_region.as_ref().map_or("", |r| r.id.as_str())
+        }),
+        (AggregateKind::IncomeLevel, |c| &c.income_level.id),
+        (AggregateKind::LendingType, |c| &c.lending_type.id),
+    ];
+    for (kind, group) in groupings {
+        let members = member_ids(countries, |c| group(c).eq_ignore_ascii_case(id));
+        if !members.is_empty() {
+            return (kind, Some(members));
+        }
+    }
+
+    if let Some((_, parts)) = COMPOSITE_INCOME_LEVELS
+        .iter()
+        .find(|(group, _)| *group == id)
+    {
+        let members = member_ids(countries, |c| parts.contains(&c.income_level.id.as_str()));
+        return (AggregateKind::IncomeLevel, Some(members));
+    }
+
+    (AggregateKind::Other, None)
+}
+
+fn member_ids(countries: &[Country], include: impl Fn(&Country) -> bool) -> Vec<String> {
+    countries
+        .iter()
+        .filter(|c| include(c))
+        .map(|c| c.id.clone())
+        .collect()
+}
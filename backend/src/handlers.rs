@@ -1,63 +1,825 @@
-use crate::api::ApiClient;
-use crate::data::get_indicators;
+use crate::adjust::{check_expression_unit, Adjustment, AdjustmentQuery, EXCHANGE_RATE};
+use crate::circuit_breaker::CircuitState;
+use crate::config::CompareConfig;
+use crate::correlation::{cross_section, time_series, CorrelationQuery, CorrelationResponse};
 use crate::errors::ApiError;
-use crate::models::{ComparisonRequest, ComparisonResponse, Country, IndicatorCategory};
+use crate::expr::{Expression, ExpressionQuery};
+use crate::models::{
+    ComparisonRequest, ComparisonResponse, Country, CountryData, IndicatorCatalog,
+    IndicatorSearchQuery, SeriesBatchRequest, SeriesBatchResponse, SeriesError, SeriesErrorKind,
+};
+use crate::query::{CountryFilter, SeriesQuery};
+use crate::ranking::{rank, RankingQuery};
+use crate::source::DataSource;
+use crate::stats::{summarize, SeriesSummary, StatsQuery};
+use crate::transform::TransformQuery;
+use crate::validation::{find_area, find_country, resolve_indicator, Area};
 use actix_web::{web, HttpResponse};
-use std::collections::HashMap;
-use std::sync::Arc;
+use futures::future;
+use futures::stream::{self, StreamExt};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
 
-pub async fn get_countries(
-    api_client: web::Data<Arc<ApiClient>>,
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 100;
+
+/// Exchange rate and deflator series keyed by `(country_id, indicator_id)`.
+type References = HashMap<(String, String), Result<CountryData, ApiError>>;
+
+pub async fn get_countries<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    filter: web::Query<CountryFilter>,
 ) -> Result<HttpResponse, ApiError> {
-    let countries = api_client.get_countries().await?;
+    let countries: Vec<Country> = source
+        .geography()
+        .await?
+        .countries
+        .into_iter()
+        .filter(|country| filter.matches(country))
+        .collect();
     Ok(HttpResponse::Ok().json(countries))
 }
 
-pub async fn get_indicators() -> HttpResponse {
-    let indicators = get_indicators();
-    HttpResponse::Ok().json(indicators)
+pub async fn get_country<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let geography = source.geography().await?;
+    let country = find_country(&geography.countries, &path.into_inner())?;
+    Ok(HttpResponse::Ok().json(country))
+}
+
+pub async fn get_aggregates<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+) -> Result<HttpResponse, ApiError> {
+    let geography = source.geography().await?;
+    Ok(HttpResponse::Ok().json(geography.aggregates))
+}
+
+pub async fn get_aggregate<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = path.into_inner();
+    let geography = source.geography().await?;
+    match find_area(&geography, &id)? {
+        Area::Aggregate(aggregate) => Ok(HttpResponse::Ok().json(aggregate)),
+        Area::Country(country) => Err(ApiError::InvalidQuery(format!(
+            "{} is a country, see /api/countries/{}",
+            id, country.id
+        ))),
+    }
 }
 
-pub async fn get_data(
-    api_client: web::Data<Arc<ApiClient>>,
+pub async fn get_indicators<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+) -> Result<HttpResponse, ApiError> {
+    let indicators = source.list_indicators().await?;
+    Ok(HttpResponse::Ok().json(indicators))
+}
+
+pub async fn get_indicator_catalog<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+) -> Result<HttpResponse, ApiError> {
+    let catalog = source.indicator_catalog().await?;
+    Ok(HttpResponse::Ok().json(&*catalog))
+}
+
+pub async fn search_indicators<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    query: web::Query<IndicatorSearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::InvalidQuery("q must not be empty".to_string()));
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+
+    let catalog = source.indicator_catalog().await?;
+    Ok(HttpResponse::Ok().json(catalog.search(&query.q, limit)))
+}
+
+pub async fn get_indicator<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let indicator = source.indicator_metadata(&path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(indicator))
+}
+
+pub async fn get_data<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    path: web::Path<(String, String)>,
+    query: web::Query<SeriesQuery>,
+    adjust: web::Query<AdjustmentQuery>,
+    transform: web::Query<TransformQuery>,
+    stats: web::Query<StatsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (country_id, indicator_id) = path.into_inner();
+    let mut data = load_series(
+        &**source,
+        &country_id,
+        &indicator_id,
+        &query,
+        &adjust,
+        &transform,
+    )
+    .await?;
+    if stats.stats {
+        data.stats = summarize(&data.data);
+    }
+    Ok(HttpResponse::Ok().json(data))
+}
+
+pub async fn get_stats<S: DataSource + ?Sized>(
+    source: web::Data<S>,
     path: web::Path<(String, String)>,
+    query: web::Query<SeriesQuery>,
+    adjust: web::Query<AdjustmentQuery>,
+    transform: web::Query<TransformQuery>,
 ) -> Result<HttpResponse, ApiError> {
     let (country_id, indicator_id) = path.into_inner();
-    let data = api_client
-        .get_country_data(&country_id, &indicator_id)
+    let data = load_series(
+        &**source,
+        &country_id,
+        &indicator_id,
+        &query,
+        &adjust,
+        &transform,
+    )
+    .await?;
+    let stats = summarize(&data.data).ok_or(ApiError::NoDataAvailable)?;
+    Ok(HttpResponse::Ok().json(SeriesSummary {
+        country_id: data.country_id,
+        country_name: data.country_name,
+        indicator_id: data.indicator_id,
+        indicator_name: data.indicator_name,
+        adjustment: data.adjustment,
+        transform: data.transform,
+        stats,
+    }))
+}
+
+pub async fn get_rankings<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    path: web::Path<String>,
+    query: web::Query<RankingQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let indicator_id = path.into_inner();
+    let series_query = query.series_query();
+    series_query.validate()?;
+
+    let indicators = source.indicator_catalog().await?;
+    let indicator = resolve_indicator(&indicators, &indicator_id)?;
+    let geography = source.geography().await?;
+
+    let series = source
+        .fetch_cross_section(&indicator.id, &series_query)
         .await?;
+    let ranking = rank(&indicator, &query, &geography, &series)?;
+    if ranking.rankings.is_empty() {
+        return Err(ApiError::NoDataAvailable);
+    }
+    Ok(HttpResponse::Ok().json(ranking))
+}
+
+pub async fn get_correlation<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    query: web::Query<CorrelationQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let series_query = query.series_query()?;
+
+    let indicators = source.indicator_catalog().await?;
+    let x = resolve_indicator(&indicators, &query.x)?;
+    let y = resolve_indicator(&indicators, &query.y)?;
+    let geography = source.geography().await?;
+
+    let response = match &query.country {
+        Some(country_id) => {
+            let area = find_area(&geography, country_id)?;
+            let (x_data, y_data) = future::try_join(
+                source.fetch_series(area.id(), &x.id, &series_query),
+                source.fetch_series(area.id(), &y.id, &series_query),
+            )
+            .await?;
+            CorrelationResponse::new(
+                &x,
+                &y,
+                Some(area.id().to_string()),
+                time_series(&x_data, &y_data),
+                x_data.stale || y_data.stale,
+            )?
+        }
+        None => {
+            let (x_series, y_series) = future::try_join(
+                source.fetch_cross_section(&x.id, &series_query),
+                source.fetch_cross_section(&y.id, &series_query),
+            )
+            .await?;
+            let stale = x_series.iter().chain(&y_series).any(|data| data.stale);
+            CorrelationResponse::new(
+                &x,
+                &y,
+                None,
+                cross_section(&geography, &x_series, &y_series),
+                stale,
+            )?
+        }
+    };
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// Resolves and fetches one series, then applies the requested adjustment
+/// and transforms.
+async fn load_series<S: DataSource + ?Sized>(
+    source: &S,
+    country_id: &str,
+    indicator_id: &str,
+    query: &SeriesQuery,
+    adjust: &AdjustmentQuery,
+    transform: &TransformQuery,
+) -> Result<CountryData, ApiError> {
+    query.validate()?;
+    let adjustment = adjust.adjustment()?;
+    let pipeline = transform.pipeline()?;
+
+    let geography = source.geography().await?;
+    let area = find_area(&geography, country_id)?;
+    let indicators = source.indicator_catalog().await?;
+    let indicator = resolve_indicator(&indicators, indicator_id)?;
+
+    let data = source.fetch_series(area.id(), &indicator.id, query).await?;
+    let data = adjust_series(source, adjustment.as_ref(), data).await?;
+    pipeline.apply(data)
+}
+
+/// A series derived from others, e.g. `?expr=NY.GDP.MKTP.CD/SP.POP.TOTL`.
+/// `mrv`/`mrnev` apply to the derived series rather than its components, so
+/// they count years for which every component has a value.
+pub async fn get_derived_data<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    path: web::Path<String>,
+    expr: web::Query<ExpressionQuery>,
+    query: web::Query<SeriesQuery>,
+    adjust: web::Query<AdjustmentQuery>,
+    transform: web::Query<TransformQuery>,
+    stats: web::Query<StatsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+    let adjustment = adjust.adjustment()?;
+    let pipeline = transform.pipeline()?;
+
+    let geography = source.geography().await?;
+    let area = find_area(&geography, &path.into_inner())?;
+    let catalog = source.indicator_catalog().await?;
+    let expression = resolve_expression(&catalog, &expr.expr)?;
+
+    let component_query = SeriesQuery {
+        mrv: None,
+        mrnev: None,
+        ..query.0.clone()
+    };
+    let ids = expression.indicator_ids();
+    let components = future::try_join_all(
+        ids.iter()
+            .map(|id| source.fetch_series(area.id(), id, &component_query)),
+    )
+    .await?;
+    let series: HashMap<String, CountryData> = ids
+        .into_iter()
+        .map(str::to_string)
+        .zip(components)
+        .collect();
+    if adjustment.is_some() {
+        check_expression_unit(&expression, &series)?;
+    }
+
+    let data = query.apply(expression.evaluate(&series)?)?;
+    let data = adjust_series(&**source, adjustment.as_ref(), data).await?;
+    let mut data = pipeline.apply(data)?;
+    if stats.stats {
+        data.stats = summarize(&data.data);
+    }
     Ok(HttpResponse::Ok().json(data))
 }
 
-pub async fn compare_countries(
-    api_client: web::Data<Arc<ApiClient>>,
+/// Converts a current US$ series into the requested currency and prices,
+/// fetching the exchange rate and deflator series it needs.
+async fn adjust_series<S: DataSource + ?Sized>(
+    source: &S,
+    adjustment: Option<&Adjustment>,
+    data: CountryData,
+) -> Result<CountryData, ApiError> {
+    let Some(adjustment) = adjustment else {
+        return Ok(data);
+    };
+
+    let reference = |country_id: String, indicator_id: String| async move {
+        source
+            .fetch_series(&country_id, &indicator_id, &SeriesQuery::default())
+            .await
+            .map_err(|e| reference_error(&country_id, &indicator_id, e))
+    };
+
+    let rates = match adjustment.rate_country(&data.country_id) {
+        Some(country_id) => Some(reference(country_id, EXCHANGE_RATE.to_string()).await?),
+        None => None,
+    };
+    let deflator = match adjustment.deflator(&data.country_id) {
+        Some((country_id, indicator_id)) => Some(reference(country_id, indicator_id).await?),
+        None => None,
+    };
+    adjustment.apply(data, rates.as_ref(), deflator.as_ref())
+}
+
+/// Fetches the exchange rate and deflator series `adjustment` needs for each
+/// of `country_ids`, one batch per indicator and at most `max_concurrency`
+/// batches at a time.
+async fn fetch_references<S: DataSource + ?Sized>(
+    source: &S,
+    adjustment: &Adjustment,
+    country_ids: &[String],
+    max_concurrency: usize,
+) -> References {
+    let mut wanted: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for country_id in country_ids {
+        for (reference_country, indicator_id) in adjustment.references(country_id) {
+            let countries = wanted.entry(indicator_id).or_default();
+            if !countries.contains(&reference_country) {
+                countries.push(reference_country);
+            }
+        }
+    }
+
+    let batches: Vec<_> = stream::iter(wanted)
+        .map(|(indicator_id, countries)| async move {
+            let series = source
+                .fetch_series_batch(&countries, &indicator_id, &SeriesQuery::default())
+                .await;
+            (indicator_id, series)
+        })
+        .buffered(max_concurrency)
+        .collect()
+        .await;
+
+    batches
+        .into_iter()
+        .flat_map(|(indicator_id, series)| {
+            series
+                .into_iter()
+                .map(move |(country_id, result)| ((country_id, indicator_id.clone()), result))
+        })
+        .collect()
+}
+
+/// `adjust_series` with the reference series already fetched.
+fn adjust_with(
+    adjustment: &Adjustment,
+    data: CountryData,
+    references: &References,
+) -> Result<CountryData, ApiError> {
+    let reference = |key: (String, String)| match references.get(&key) {
+        Some(Ok(data)) => Ok(data),
+        Some(Err(e)) => Err(reference_error(&key.0, &key.1, e.clone())),
+        None => Err(reference_error(&key.0, &key.1, ApiError::NoDataAvailable)),
+    };
+    let rates = adjustment
+        .rate_country(&data.country_id)
+        .map(|country_id| reference((country_id, EXCHANGE_RATE.to_string())))
+        .transpose()?;
+    let deflator = adjustment
+        .deflator(&data.country_id)
+        .map(reference)
+        .transpose()?;
+    adjustment.apply(data, rates, deflator)
+}
+
+/// A missing exchange rate or deflator makes the adjustment impossible rather
+/// than the requested series unknown.
+fn reference_error(country_id: &str, indicator_id: &str, err: ApiError) -> ApiError {
+    match err {
+        ApiError::NoDataAvailable
+        | ApiError::CountryNotFound { .. }
+        | ApiError::IndicatorNotFound { .. } => ApiError::TransformFailed(format!(
+            "{} unavailable for {}: {}",
+            indicator_id, country_id, err
+        )),
+        err => err,
+    }
+}
+
+fn resolve_expression(catalog: &IndicatorCatalog, spec: &str) -> Result<Expression, ApiError> {
+    spec.parse::<Expression>()?
+        .resolve(|id| resolve_indicator(catalog, id).map(|indicator| indicator.id.clone()))
+}
+
+pub async fn get_series_batch<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    params: web::Query<SeriesBatchRequest>,
+    query: web::Query<SeriesQuery>,
+    transform: web::Query<TransformQuery>,
+) -> Result<HttpResponse, ApiError> {
+    query.validate()?;
+    let pipeline = transform.pipeline()?;
+
+    let indicators = source.indicator_catalog().await?;
+    let indicator = resolve_indicator(&indicators, &params.indicator)?;
+    let geography = source.geography().await?;
+
+    let mut country_ids: Vec<String> = Vec::new();
+    let mut errors = Vec::new();
+    for country_id in params
+        .countries
+        .split([';', ','])
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+    {
+        match find_area(&geography, country_id) {
+            Ok(area) if !country_ids.iter().any(|id| id == area.id()) => {
+                country_ids.push(area.id().to_string());
+            }
+            Ok(_) => {}
+            Err(e) => errors.push(SeriesError {
+                country_id: country_id.to_string(),
+                indicator_id: None,
+                kind: SeriesErrorKind::NotFound,
+                message: e.to_string(),
+            }),
+        }
+    }
+    if country_ids.is_empty() && errors.is_empty() {
+        return Err(ApiError::InvalidQuery(
+            "countries must list at least one country".to_string(),
+        ));
+    }
+
+    let mut data = Vec::new();
+    for (country_id, result) in source
+        .fetch_series_batch(&country_ids, &indicator.id, &query)
+        .await
+    {
+        match result.and_then(|series| pipeline.apply(series)) {
+            Ok(series) => data.push(series),
+            Err(e) => errors.push(SeriesError {
+                country_id,
+                indicator_id: Some(indicator.id.clone()),
+                kind: series_error_kind(&e),
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(SeriesBatchResponse {
+        indicator_id: indicator.id.clone(),
+        data,
+        errors,
+    }))
+}
+
+pub async fn compare_countries<S: DataSource + ?Sized>(
+    source: web::Data<S>,
+    settings: web::Data<CompareConfig>,
+    adjust: web::Query<AdjustmentQuery>,
+    transform: web::Query<TransformQuery>,
+    stats: web::Query<StatsQuery>,
     req: web::Json<ComparisonRequest>,
 ) -> Result<HttpResponse, ApiError> {
+    let adjustment = adjust.adjustment()?;
+    let pipeline = transform.pipeline()?;
+    let geography = source.geography().await?;
+    let indicators = source.indicator_catalog().await?;
     let mut response_countries = Vec::new();
-    let mut data_map: HashMap<String, HashMap<String, Vec<_>>> = HashMap::new();
+    let mut response_aggregates = Vec::new();
+    let mut country_ids: Vec<String> = Vec::new();
+    let mut errors = Vec::new();
 
     for country_id in &req.countries {
-        let countries = api_client.get_countries().await?;
-        if let Some(country) = countries.iter().find(|c| &c.id == country_id) {
-            response_countries.push(country.clone());
+        match find_area(&geography, country_id) {
+            Ok(area) if !country_ids.iter().any(|id| id == area.id()) => {
+                country_ids.push(area.id().to_string());
+                match area {
+                    Area::Country(country) => response_countries.push(country.clone()),
+                    Area::Aggregate(aggregate) => response_aggregates.push(aggregate.clone()),
+                }
+            }
+            Ok(_) => {}
+            Err(e) => errors.push(SeriesError {
+                country_id: country_id.clone(),
+                indicator_id: None,
+                kind: SeriesErrorKind::NotFound,
+                message: e.to_string(),
+            }),
         }
     }
 
-    for country_id in &req.countries {
-        for indicator_id in &req.indicators {
-            if let Ok(data) = api_client.get_country_data(country_id, indicator_id).await {
-                data_map
-                    .entry(country_id.clone())
-                    .or_insert_with(HashMap::new)
-                    .insert(indicator_id.clone(), data.data);
+    // Each entry is an indicator id or a derived-series expression.
+    let mut expressions = Vec::new();
+    for indicator_id in &req.indicators {
+        match resolve_expression(&indicators, indicator_id) {
+            Ok(expression) => expressions.push(expression),
+            Err(e) => errors.extend(country_ids.iter().map(|country_id| SeriesError {
+                country_id: country_id.clone(),
+                indicator_id: Some(indicator_id.clone()),
+                kind: series_error_kind(&e),
+                message: e.to_string(),
+            })),
+        }
+    }
+
+    let mut indicator_ids: Vec<&str> = Vec::new();
+    for id in expressions.iter().flat_map(Expression::indicator_ids) {
+        if !indicator_ids.contains(&id) {
+            indicator_ids.push(id);
+        }
+    }
+
+    // One batch request per indicator covers every country.
+    let results: Vec<_> = stream::iter(&indicator_ids)
+        .map(|indicator_id| {
+            let source = source.clone();
+            let country_ids = &country_ids;
+            async move {
+                let series = source
+                    .fetch_series_batch(country_ids, indicator_id, &SeriesQuery::default())
+                    .await;
+                (indicator_id, series)
+            }
+        })
+        .buffered(settings.max_concurrency)
+        .collect()
+        .await;
+
+    let mut fetched: HashMap<&str, HashMap<String, Result<CountryData, ApiError>>> =
+        HashMap::new();
+    for (indicator_id, series) in results {
+        fetched.insert(indicator_id, series.into_iter().collect());
+    }
+
+    let references = match &adjustment {
+        Some(adjustment) => {
+            fetch_references(
+                &**source,
+                adjustment,
+                &country_ids,
+                settings.max_concurrency,
+            )
+            .await
+        }
+        None => References::new(),
+    };
+
+    let mut data_map: BTreeMap<String, BTreeMap<String, Vec<_>>> = BTreeMap::new();
+    let mut stats_map: BTreeMap<String, BTreeMap<String, _>> = BTreeMap::new();
+    for expression in &expressions {
+        let key = expression.to_string();
+        for country_id in &country_ids {
+            let component = |id: &str| {
+                fetched
+                    .get(id)
+                    .and_then(|series| series.get(country_id))
+                    .cloned()
+                    .unwrap_or(Err(ApiError::NoDataAvailable))
+            };
+            let result = match expression.as_indicator() {
+                Some(id) => component(id),
+                None => expression
+                    .indicator_ids()
+                    .into_iter()
+                    .map(|id| component(id).map(|data| (id.to_string(), data)))
+                    .collect::<Result<HashMap<_, _>, _>>()
+                    .and_then(|series| {
+                        if adjustment.is_some() {
+                            check_expression_unit(expression, &series)?;
+                        }
+                        expression.evaluate(&series)
+                    }),
+            };
+
+            let result = match (result, &adjustment) {
+                (Ok(data), Some(adjustment)) => adjust_with(adjustment, data, &references),
+                (result, _) => result,
+            };
+
+            match result.and_then(|data| pipeline.apply(data)) {
+                Ok(data) => {
+                    if let Some(summary) = summarize(&data.data).filter(|_| stats.stats) {
+                        stats_map
+                            .entry(country_id.clone())
+                            .or_default()
+                            .insert(key.clone(), summary);
+                    }
+                    data_map
+                        .entry(country_id.clone())
+                        .or_default()
+                        .insert(key.clone(), data.data);
+                }
+                Err(e) => errors.push(SeriesError {
+                    country_id: country_id.clone(),
+                    indicator_id: Some(key.clone()),
+                    kind: series_error_kind(&e),
+                    message: e.to_string(),
+                }),
             }
         }
     }
 
     let comparison = ComparisonResponse {
         countries: response_countries,
+        aggregates: response_aggregates,
         data: data_map,
+        stats: stats_map,
+        errors,
     };
 
     Ok(HttpResponse::Ok().json(comparison))
 }
+
+fn series_error_kind(err: &ApiError) -> SeriesErrorKind {
+    match err {
+        ApiError::CountryNotFound { .. }
+        | ApiError::IndicatorNotFound { .. }
+        | ApiError::FixtureNotFound(_) => SeriesErrorKind::NotFound,
+        ApiError::NoDataAvailable => SeriesErrorKind::NoData,
+        ApiError::TransformFailed(_) => SeriesErrorKind::Transform,
+        ApiError::InvalidQuery(_) => SeriesErrorKind::Invalid,
+        _ => SeriesErrorKind::Upstream,
+    }
+}
+
+pub async fn get_cache_stats<S: DataSource + ?Sized>(source: web::Data<S>) -> HttpResponse {
+    HttpResponse::Ok().json(source.cache_stats())
+}
+
+pub async fn get_health<S: DataSource + ?Sized>(source: web::Data<S>) -> HttpResponse {
+    let circuit = source.circuit_status();
+    let status = match circuit.as_ref().map(|c| c.state) {
+        Some(CircuitState::Open) | Some(CircuitState::HalfOpen) => "degraded",
+        _ => "ok",
+    };
+
+    HttpResponse::Ok().json(json!({
+        "status": status,
+        "circuit": circuit,
+        "cache": source.cache_stats(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data;
+    use crate::models::{Geography, IndicatorCategory};
+    use crate::source::BatchEntry;
+    use actix_web::body::to_bytes;
+    use async_trait::async_trait;
+    use serde_json::Value;
+    use std::sync::Mutex;
+
+    /// Serves a flat series for every country and records each request.
+    #[derive(Default)]
+    struct FakeSource {
+        requests: Mutex<Vec<String>>,
+    }
+
+    fn series(country_id: &str, indicator_id: &str) -> CountryData {
+        let indicator_name = match indicator_id {
+            "NY.GDP.MKTP.CD" => "GDP (current US$)",
+            "NE.EXP.GNFS.CD" => "Exports of goods and services (current US$)",
+            _ => indicator_id,
+        };
+        CountryData {
+            indicator_name: indicator_name.to_string(),
+            ..CountryData::test_series(country_id, indicator_id, &[("2020", 2.0)])
+        }
+    }
+
+    #[async_trait]
+    impl DataSource for FakeSource {
+        async fn geography(&self) -> Result<Geography, ApiError> {
+            let countries = ["USA", "GBR", "FRA"]
+                .iter()
+                .map(|id| {
+                    serde_json::from_value(json!({
+                        "id": id,
+                        "name": id,
+                        "region": "",
+                        "capital": "",
+                    }))
+                    .unwrap()
+                })
+                .collect();
+            Ok(Geography {
+                countries,
+                aggregates: Vec::new(),
+            })
+        }
+
+        async fn list_indicators(&self) -> Result<IndicatorCategory, ApiError> {
+            Ok(data::get_indicators())
+        }
+
+        async fn fetch_series(
+            &self,
+            country_id: &str,
+            indicator_id: &str,
+            _query: &SeriesQuery,
+        ) -> Result<CountryData, ApiError> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push(format!("{}/{}", country_id, indicator_id));
+            Ok(series(country_id, indicator_id))
+        }
+
+        async fn fetch_series_batch(
+            &self,
+            country_ids: &[String],
+            indicator_id: &str,
+            _query: &SeriesQuery,
+        ) -> Vec<BatchEntry> {
+            let request = format!("{}/{}", country_ids.join(";"), indicator_id);
+            self.requests.lock().unwrap().push(request);
+            country_ids
+                .iter()
+                .map(|id| (id.clone(), Ok(series(id, indicator_id))))
+                .collect()
+        }
+    }
+
+    async fn compare(
+        source: &web::Data<FakeSource>,
+        adjust: AdjustmentQuery,
+        countries: &[&str],
+        indicators: &[&str],
+    ) -> Value {
+        let response = compare_countries(
+            source.clone(),
+            web::Data::new(CompareConfig::default()),
+            web::Query(adjust),
+            web::Query(TransformQuery::default()),
+            web::Query(StatsQuery::default()),
+            web::Json(ComparisonRequest {
+                countries: countries.iter().map(|c| c.to_string()).collect(),
+                indicators: indicators.iter().map(|i| i.to_string()).collect(),
+            }),
+        )
+        .await
+        .unwrap();
+        serde_json::from_slice(&to_bytes(response.into_body()).await.unwrap()).unwrap()
+    }
+
+    #[actix_rt::test]
+    async fn compare_batches_exchange_rates() {
+        let source = web::Data::new(FakeSource::default());
+        let adjust = AdjustmentQuery {
+            currency: Some("LCU".to_string()),
+            ..AdjustmentQuery::default()
+        };
+        let body = compare(&source, adjust, &["USA", "GBR", "FRA"], &["NY.GDP.MKTP.CD"]).await;
+
+        assert_eq!(body["data"]["GBR"]["NY.GDP.MKTP.CD"][0]["value"], 4.0);
+        let mut requests = source.requests.lock().unwrap().clone();
+        requests.sort();
+        assert_eq!(
+            requests,
+            ["USA;GBR;FRA/NY.GDP.MKTP.CD", "USA;GBR;FRA/PA.NUS.FCRF"]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn compare_ignores_repeated_countries() {
+        let source = web::Data::new(FakeSource::default());
+        let body = compare(
+            &source,
+            AdjustmentQuery::default(),
+            &["USA", "usa"],
+            &["NY.GDP.MKTP.CD"],
+        )
+        .await;
+
+        assert_eq!(body["countries"].as_array().unwrap().len(), 1);
+        assert_eq!(body["data"]["USA"]["NY.GDP.MKTP.CD"][0]["value"], 2.0);
+        let errors = body.get("errors").and_then(Value::as_array);
+        assert!(errors.is_none_or(|errors| errors.is_empty()));
+    }
+
+    #[actix_rt::test]
+    async fn compare_rejects_adjusting_us_dollar_ratios() {
+        let source = web::Data::new(FakeSource::default());
+        let adjust = AdjustmentQuery {
+            currency: Some("EUR".to_string()),
+            ..AdjustmentQuery::default()
+        };
+        let body = compare(
+            &source,
+            adjust,
+            &["USA"],
+            &["NE.EXP.GNFS.CD/NY.GDP.MKTP.CD"],
+        )
+        .await;
+
+        assert_eq!(body["errors"][0]["kind"], "transform");
+    }
+}
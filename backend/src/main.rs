@@ -1,35 +1,128 @@
+mod adjust;
 mod api;
+mod cache;
+mod catalog;
+mod circuit_breaker;
+mod config;
+mod correlation;
 mod data;
 mod errors;
+mod expr;
+mod fixtures;
+mod geography;
 mod handlers;
 mod models;
+mod query;
+mod ranking;
+mod retry;
+mod search;
+mod source;
+mod stats;
+mod store;
+mod text;
+mod transform;
+mod validation;
+mod worldbank;
 
 use actix_web::{web, App, HttpServer, middleware};
-use log::info;
+use config::{Config, SourceKind};
+use log::{info, warn};
+use source::DataSource;
+use std::io;
 use std::sync::Arc;
 
 #[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+async fn main() -> io::Result<()> {
+    let config = Config::load().map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
 
-    let api_state = Arc::new(api::ApiClient::new());
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or(&config.logging.level));
 
-    info!("Starting World Bank API server on http://0.0.0.0:8080");
+    let source = build_source(&config)?;
+    let compare_config = web::Data::new(config.compare.clone());
+    let (host, port) = config.bind_address();
+
+    info!("Starting World Bank API server on http://{}:{}", host, port);
 
     HttpServer::new(move || {
         App::new()
-            .app_data(web::Data::new(api_state.clone()))
+            .app_data(web::Data::from(source.clone()))
+            .app_data(compare_config.clone())
             .wrap(middleware::Logger::default())
             .wrap(actix_web::middleware::NormalizePath::trim())
-            .service(
-                web::scope("/api")
-                    .route("/countries", web::get().to(handlers::get_countries))
-                    .route("/indicators", web::get().to(handlers::get_indicators))
-                    .route("/data/{country_id}/{indicator_id}", web::get().to(handlers::get_data))
-                    .route("/compare", web::post().to(handlers::compare_countries))
-            )
+            .service(web::scope("/api").configure(api_routes::<dyn DataSource>))
     })
-    .bind("0.0.0.0:8080")?
+    .bind((host, port))?
     .run()
     .await
 }
+
+fn build_source(config: &Config) -> io::Result<Arc<dyn DataSource>> {
+    let fixtures_dir = &config.source.fixtures_dir;
+    if config.source.kind == SourceKind::Fixtures {
+        info!("Serving recorded fixtures from {}", fixtures_dir.display());
+        return Ok(Arc::new(fixtures::FixtureSource::new(fixtures_dir)));
+    }
+
+    let mut client = api::ApiClient::new(&config.upstream, config.cache.to_cache_config())
+        .map_err(io::Error::other)?;
+
+    if !config.store.path.is_empty() {
+        match store::Store::open(&config.store.path) {
+            Ok(store) => {
+                info!("Using persistent store at {}", config.store.path);
+                client = client.with_store(store, config.store.max_age());
+            }
+            Err(e) => {
+                warn!(
+                    "Persistent store disabled, failed to open {}: {}",
+                    config.store.path, e
+                );
+            }
+        }
+    }
+
+    if config.source.kind == SourceKind::Record {
+        info!("Recording upstream responses into {}", fixtures_dir.display());
+        client = client.with_recorder(fixtures::FixtureRecorder::new(fixtures_dir));
+    }
+
+    Ok(Arc::new(client))
+}
+
+fn api_routes<S: DataSource + ?Sized + 'static>(cfg: &mut web::ServiceConfig) {
+    cfg.route("/countries", web::get().to(handlers::get_countries::<S>))
+        .route("/countries/{country_id}", web::get().to(handlers::get_country::<S>))
+        .route("/aggregates", web::get().to(handlers::get_aggregates::<S>))
+        .route(
+            "/aggregates/{aggregate_id}",
+            web::get().to(handlers::get_aggregate::<S>),
+        )
+        .route("/indicators", web::get().to(handlers::get_indicators::<S>))
+        .route(
+            "/indicators/catalog",
+            web::get().to(handlers::get_indicator_catalog::<S>),
+        )
+        .route(
+            "/indicators/search",
+            web::get().to(handlers::search_indicators::<S>),
+        )
+        .route(
+            "/indicators/{indicator_id}",
+            web::get().to(handlers::get_indicator::<S>),
+        )
+        .route("/data/{country_id}", web::get().to(handlers::get_derived_data::<S>))
+        .route("/data/{country_id}/{indicator_id}", web::get().to(handlers::get_data::<S>))
+        .route(
+            "/stats/{country_id}/{indicator_id}",
+            web::get().to(handlers::get_stats::<S>),
+        )
+        .route(
+            "/rankings/{indicator_id}",
+            web::get().to(handlers::get_rankings::<S>),
+        )
+        .route("/correlation", web::get().to(handlers::get_correlation::<S>))
+        .route("/series", web::get().to(handlers::get_series_batch::<S>))
+        .route("/compare", web::post().to(handlers::compare_countries::<S>))
+        .route("/cache/stats", web::get().to(handlers::get_cache_stats::<S>))
+        .route("/health", web::get().to(handlers::get_health::<S>));
+}
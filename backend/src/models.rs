@@ -1,21 +1,100 @@
+use chrono::{DateTime, Utc};
+use crate::search::SearchIndex;
+use crate::stats::SeriesStats;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
 
+/// A country, keyed by its ISO3 code.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Country {
     pub id: String,
+    #[serde(default)]
+    pub iso2_code: String,
     pub name: String,
     pub region: String,
+    #[serde(default)]
+    pub region_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_region: Option<Classification>,
+    #[serde(default)]
+    pub income_level: Classification,
+    #[serde(default)]
+    pub lending_type: Classification,
     pub capital: String,
-    pub code: String,
+    #[serde(default)]
+    pub longitude: Option<f64>,
+    #[serde(default)]
+    pub latitude: Option<f64>,
 }
 
+/// A regional, income or other grouping of countries reported by the World
+/// Bank, e.g. `WLD` (World) or `LIC` (Low income). Accepted wherever a
+/// country id is.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Aggregate {
+    pub id: String,
+    #[serde(default)]
+    pub iso2_code: String,
+    pub name: String,
+    pub kind: AggregateKind,
+    /// ISO3 ids of the member countries, when they can be derived from the
+    /// country classifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateKind {
+    World,
+    Region,
+    AdminRegion,
+    IncomeLevel,
+    LendingType,
+    Other,
+}
+
+/// The World Bank country list, split into countries and aggregates.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Geography {
+    pub countries: Vec<Country>,
+    #[serde(default)]
+    pub aggregates: Vec<Aggregate>,
+}
+
+/// A World Bank grouping such as an income level (`HIC`, "High income") or
+/// lending type (`IBD`, "IBRD").
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Classification {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Indicator {
     pub id: String,
     pub name: String,
     pub category: String,
     pub unit: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source_note: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source_organization: String,
+    /// The World Bank database the indicator comes from.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source_database: String,
+    /// Names of the World Bank topics the indicator is filed under.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<String>,
+    /// Long definition from the series metadata endpoint. This and the fields
+    /// below are only filled in by `/api/indicators/{id}`.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub definition: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub periodicity: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub aggregation_method: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +105,82 @@ pub struct IndicatorCategory {
     pub environmental: Vec<Indicator>,
 }
 
+impl IndicatorCategory {
+    pub fn iter(&self) -> impl Iterator<Item = &Indicator> + Clone {
+        self.economic
+            .iter()
+            .chain(&self.demographic)
+            .chain(&self.social)
+            .chain(&self.environmental)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Topic {
+    pub id: String,
+    pub name: String,
+    pub category: String,
+}
+
+/// Every indicator that can be requested, with the curated list served by
+/// `/api/indicators` kept as `featured`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorCatalog {
+    pub featured: IndicatorCategory,
+    pub topics: Vec<Topic>,
+    pub indicators: Vec<Indicator>,
+    /// Set when only the curated indicators are known, in which case other
+    /// ids are passed upstream unchecked rather than rejected.
+    #[serde(skip)]
+    pub partial: bool,
+    /// Built on first search and kept for the lifetime of the catalog.
+    #[serde(skip)]
+    pub search_index: OnceLock<SearchIndex>,
+}
+
+impl IndicatorCatalog {
+    /// A catalog of the curated indicators alone, used when the full catalog
+    /// cannot be loaded.
+    pub fn featured_only(featured: IndicatorCategory) -> Self {
+        IndicatorCatalog {
+            indicators: featured.iter().cloned().collect(),
+            topics: Vec::new(),
+            featured,
+            partial: true,
+            search_index: OnceLock::new(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Indicator> + Clone {
+        self.indicators.iter()
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<IndicatorMatch> {
+        self.search_index
+            .get_or_init(|| SearchIndex::build(&self.indicators))
+            .search(&self.indicators, query, limit)
+            .into_iter()
+            .map(|(position, score)| IndicatorMatch {
+                indicator: self.indicators[position].clone(),
+                score,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorMatch {
+    #[serde(flatten)]
+    pub indicator: Indicator,
+    pub score: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndicatorSearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataPoint {
     pub year: String,
@@ -39,6 +194,45 @@ pub struct CountryData {
     pub indicator_id: String,
     pub indicator_name: String,
     pub data: Vec<DataPoint>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refreshed_at: Option<DateTime<Utc>>,
+    /// Set when upstream was unavailable and older cached data was served.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+    /// Currency and price basis of `data` when converted from current US$.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<String>,
+    /// The transform pipeline applied to `data`, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transform: Option<String>,
+    /// Summary of `data`, included on request with `stats=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stats: Option<SeriesStats>,
+}
+
+#[cfg(test)]
+impl CountryData {
+    /// A series of `(year, value)` points named after its ids.
+    pub fn test_series(country_id: &str, indicator_id: &str, points: &[(&str, f64)]) -> Self {
+        CountryData {
+            country_id: country_id.to_string(),
+            country_name: country_id.to_string(),
+            indicator_id: indicator_id.to_string(),
+            indicator_name: indicator_id.to_string(),
+            data: points
+                .iter()
+                .map(|(year, value)| DataPoint {
+                    year: year.to_string(),
+                    value: *value,
+                })
+                .collect(),
+            refreshed_at: None,
+            stale: false,
+            adjustment: None,
+            transform: None,
+            stats: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,7 +244,50 @@ pub struct ComparisonRequest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComparisonResponse {
     pub countries: Vec<Country>,
-    pub data: HashMap<String, HashMap<String, Vec<DataPoint>>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aggregates: Vec<Aggregate>,
+    pub data: BTreeMap<String, BTreeMap<String, Vec<DataPoint>>>,
+    /// Summaries keyed like `data`, included on request with `stats=true`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub stats: BTreeMap<String, BTreeMap<String, SeriesStats>>,
+    #[serde(default)]
+    pub errors: Vec<SeriesError>,
+}
+
+/// Query string of `/api/series`. `countries` is a `;` or `,` separated list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesBatchRequest {
+    pub countries: String,
+    pub indicator: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesBatchResponse {
+    pub indicator_id: String,
+    pub data: Vec<CountryData>,
+    #[serde(default)]
+    pub errors: Vec<SeriesError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeriesErrorKind {
+    NotFound,
+    NoData,
+    Upstream,
+    Transform,
+    Invalid,
+}
+
+/// A country, or country/indicator pair, that could not be included in a
+/// comparison or batch. `indicator_id` is absent when the country itself is unknown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesError {
+    pub country_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indicator_id: Option<String>,
+    pub kind: SeriesErrorKind,
+    pub message: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,222 @@
+use crate::errors::ApiError;
+use crate::models::{Classification, Country, CountryData, DataPoint};
+use serde::{Deserialize, Serialize};
+
+/// The World Bank `date` option takes a single year or a closed `start:end`
+/// range, so a range open on one side is closed with the bound below, which
+/// lies outside any published series.
+const EARLIEST_YEAR: i32 = 1900;
+const LATEST_YEAR: i32 = 9999;
+
+/// Optional narrowing of a series: an inclusive `from`/`to` year range, and
+/// either the `mrv` most recent values or the `mrnev` most recent non-empty
+/// values. Mirrors the World Bank `date`, `mrv` and `mrnev` options.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeriesQuery {
+    pub from: Option<i32>,
+    pub to: Option<i32>,
+    pub mrv: Option<u32>,
+    pub mrnev: Option<u32>,
+}
+
+impl SeriesQuery {
+    pub fn is_empty(&self) -> bool {
+        self == &SeriesQuery::default()
+    }
+
+    pub fn validate(&self) -> Result<(), ApiError> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(ApiError::InvalidQuery(format!(
+                    "from ({}) must not be after to ({})",
+                    from, to
+                )));
+            }
+        }
+        if self.mrv.is_some() && self.mrnev.is_some() {
+            return Err(ApiError::InvalidQuery(
+                "mrv and mrnev cannot be combined".to_string(),
+            ));
+        }
+        if self.mrv == Some(0) || self.mrnev == Some(0) {
+            return Err(ApiError::InvalidQuery(
+                "mrv and mrnev must be greater than 0".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// World Bank query parameters for this query.
+    pub fn to_params(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        match (self.from, self.to) {
+            (Some(from), Some(to)) => params.push(("date", format!("{}:{}", from, to))),
+            (Some(from), None) => params.push(("date", format!("{}:{}", from, LATEST_YEAR))),
+            (None, Some(to)) => params.push(("date", format!("{}:{}", EARLIEST_YEAR, to))),
+            (None, None) => {}
+        }
+        if let Some(mrv) = self.mrv {
+            params.push(("mrv", mrv.to_string()));
+        }
+        if let Some(mrnev) = self.mrnev {
+            params.push(("mrnev", mrnev.to_string()));
+        }
+        params
+    }
+
+    /// Canonical form used in cache keys.
+    pub fn cache_key(&self) -> String {
+        self.to_params()
+            .iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// Whether a full series held locally answers this query exactly. Stored
+    /// series drop empty observations, so they cannot tell which periods the
+    /// World Bank's `mrv` would count; `mrv` queries go upstream instead.
+    pub fn is_local(&self) -> bool {
+        self.mrv.is_none()
+    }
+
+    /// Applies the query to a series. `mrnev` keeps the latest non-empty
+    /// points. `mrv` is only exact for a series fetched upstream with the same
+    /// `mrv`, whose empty periods are already dropped; on a full series, as
+    /// served stale, it approximates by keeping the latest non-empty points.
+    pub fn apply(&self, mut data: CountryData) -> Result<CountryData, ApiError> {
+        data.data.retain(|point| match point_year(point) {
+            Some(year) => {
+                self.from.is_none_or(|from| year >= from) && self.to.is_none_or(|to| year <= to)
+            }
+            None => self.from.is_none() && self.to.is_none(),
+        });
+
+        if let Some(latest) = self.mrv.or(self.mrnev) {
+            let skip = data.data.len().saturating_sub(latest as usize);
+            data.data.drain(..skip);
+        }
+
+        if data.data.is_empty() {
+            return Err(ApiError::NoDataAvailable);
+        }
+        Ok(data)
+    }
+}
+
+/// The calendar year of a data point; sub-annual dates such as `2020Q1` or
+/// `2020M01` count towards their year.
+pub fn point_year(point: &DataPoint) -> Option<i32> {
+    point.year.get(..4)?.parse().ok()
+}
+
+/// Filters for `/api/countries`. Each matches a classification's id or name,
+/// ignoring case, e.g. `income_level=HIC` or `income_level=high income`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CountryFilter {
+    pub region: Option<String>,
+    pub income_level: Option<String>,
+    pub lending_type: Option<String>,
+    pub admin_region: Option<String>,
+}
+
+impl CountryFilter {
+    pub fn matches(&self, country: &Country) -> bool {
+        let region = Classification {
+            id: country.region_id.clone(),
+            name: country.region.clone(),
+        };
+        matches_filter(&self.region, Some(&region))
+            && matches_filter(&self.income_level, Some(&country.income_level))
+            && matches_filter(&self.lending_type, Some(&country.lending_type))
+            && matches_filter(&self.admin_region, country.admin_region.as_ref())
+    }
+}
+
+fn matches_filter(filter: &Option<String>, value: Option<&Classification>) -> bool {
+    let Some(filter) = filter else {
+        return true;
+    };
+    value.is_some_and(|value| {
+        value.id.eq_ignore_ascii_case(filter) || value.name.eq_ignore_ascii_case(filter)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn series(years: &[&str]) -> CountryData {
+        let points: Vec<(&str, f64)> = years.iter().map(|year| (*year, 1.0)).collect();
+        CountryData::test_series("USA", "SP.POP.TOTL", &points)
+    }
+
+    fn years(data: &CountryData) -> Vec<&str> {
+        data.data.iter().map(|point| point.year.as_str()).collect()
+    }
+
+    #[test]
+    fn mrv_is_never_answered_locally() {
+        let mrv = SeriesQuery {
+            mrv: Some(3),
+            ..SeriesQuery::default()
+        };
+        let mrnev = SeriesQuery {
+            mrnev: Some(3),
+            ..SeriesQuery::default()
+        };
+        assert!(!mrv.is_local());
+        assert!(mrnev.is_local());
+        assert!(SeriesQuery::default().is_local());
+    }
+
+    #[test]
+    fn open_ranges_are_closed_for_the_date_option() {
+        let from = SeriesQuery {
+            from: Some(2010),
+            ..SeriesQuery::default()
+        };
+        let to = SeriesQuery {
+            to: Some(2015),
+            ..SeriesQuery::default()
+        };
+        assert_eq!(from.to_params(), [("date", "2010:9999".to_string())]);
+        assert_eq!(to.to_params(), [("date", "1900:2015".to_string())]);
+    }
+
+    #[test]
+    fn apply_filters_range_then_keeps_latest() {
+        let query = SeriesQuery {
+            from: Some(2001),
+            to: Some(2004),
+            mrnev: Some(2),
+            ..SeriesQuery::default()
+        };
+        let data = query
+            .apply(series(&["2000", "2001", "2002", "2003", "2004", "2005"]))
+            .unwrap();
+        assert_eq!(years(&data), ["2003", "2004"]);
+    }
+
+    #[test]
+    fn apply_counts_sub_annual_periods_towards_their_year() {
+        let query = SeriesQuery {
+            from: Some(2020),
+            ..SeriesQuery::default()
+        };
+        let data = query.apply(series(&["2019Q4", "2020Q1", "2020Q2"])).unwrap();
+        assert_eq!(years(&data), ["2020Q1", "2020Q2"]);
+    }
+
+    #[test]
+    fn apply_with_nothing_left_is_no_data() {
+        let query = SeriesQuery {
+            from: Some(2030),
+            ..SeriesQuery::default()
+        };
+        assert!(matches!(
+            query.apply(series(&["2020"])),
+            Err(ApiError::NoDataAvailable)
+        ));
+    }
+}
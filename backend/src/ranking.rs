@@ -0,0 +1,186 @@
+use crate::errors::ApiError;
+use crate::models::{Country, CountryData, Geography, Indicator};
+use crate::query::{CountryFilter, SeriesQuery};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Query parameters of `/api/rankings/{indicator_id}`. `region` and `income`
+/// select a peer group, matched like the `/api/countries` filters.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RankingQuery {
+    pub year: Option<i32>,
+    pub region: Option<String>,
+    pub income: Option<String>,
+}
+
+impl RankingQuery {
+    /// Values for `year`, or each economy's most recent non-empty value.
+    pub fn series_query(&self) -> SeriesQuery {
+        match self.year {
+            Some(year) => SeriesQuery {
+                from: Some(year),
+                to: Some(year),
+                ..SeriesQuery::default()
+            },
+            None => SeriesQuery {
+                mrnev: Some(1),
+                ..SeriesQuery::default()
+            },
+        }
+    }
+
+    pub fn peer_filter(&self) -> Option<CountryFilter> {
+        if self.region.is_none() && self.income.is_none() {
+            return None;
+        }
+        Some(CountryFilter {
+            region: self.region.clone(),
+            income_level: self.income.clone(),
+            ..CountryFilter::default()
+        })
+    }
+}
+
+/// One economy's position. `rank` and `percentile` are among all ranked
+/// economies, the `peer_` fields within the requested peer group.
+#[derive(Debug, Clone, Serialize)]
+pub struct RankingEntry {
+    pub rank: usize,
+    /// Percentage of the other ranked economies with a lower value.
+    pub percentile: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_percentile: Option<f64>,
+    pub year: String,
+    pub value: f64,
+    pub country: Country,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RankingResponse {
+    pub indicator_id: String,
+    pub indicator_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    /// Number of economies ranked.
+    pub count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peer_count: Option<usize>,
+    pub stale: bool,
+    /// Highest value first; only the peer group when one is requested.
+    pub rankings: Vec<RankingEntry>,
+}
+
+/// Ranks the economies of `geography` by their latest value in `series`,
+/// highest first. Aggregates are not ranked. Ties share the best rank.
+pub fn rank(
+    indicator: &Indicator,
+    query: &RankingQuery,
+    geography: &Geography,
+    series: &[CountryData],
+) -> Result<RankingResponse, ApiError> {
+    let filter = query.peer_filter();
+    if let Some(filter) = &filter {
+        if !geography.countries.iter().any(|c| filter.matches(c)) {
+            return Err(ApiError::InvalidQuery(
+                "no economies match the requested region and income level".to_string(),
+            ));
+        }
+    }
+
+    let countries: HashMap<String, &Country> = geography
+        .countries
+        .iter()
+        .map(|country| (country.id.to_uppercase(), country))
+        .collect();
+
+    let mut ranked: Vec<(&Country, &CountryData)> = series
+        .iter()
+        .filter(|data| !data.data.is_empty())
+        .filter_map(|data| Some((*countries.get(&data.country_id.to_uppercase())?, data)))
+        .collect();
+    ranked.sort_by(|(a, a_data), (b, b_data)| {
+        latest(b_data)
+            .total_cmp(&latest(a_data))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    let values: Vec<f64> = ranked.iter().map(|(_, data)| latest(data)).collect();
+    let peer_values: Vec<f64> = ranked
+        .iter()
+        .filter(|(country, _)| filter.as_ref().is_some_and(|f| f.matches(country)))
+        .map(|(_, data)| latest(data))
+        .collect();
+
+    let rankings: Vec<RankingEntry> = ranked
+        .iter()
+        .filter(|(country, _)| filter.as_ref().is_none_or(|f| f.matches(country)))
+        .map(|(country, data)| {
+            let point = data.data.last().expect("ranked series are not empty");
+            let (rank, percentile) = position(&values, point.value);
+            let peer = filter.as_ref().map(|_| position(&peer_values, point.value));
+            RankingEntry {
+                rank,
+                percentile,
+                peer_rank: peer.map(|(rank, _)| rank),
+                peer_percentile: peer.map(|(_, percentile)| percentile),
+                year: point.year.clone(),
+                value: point.value,
+                country: (*country).clone(),
+            }
+        })
+        .collect();
+
+    Ok(RankingResponse {
+        indicator_id: indicator.id.clone(),
+        indicator_name: indicator.name.clone(),
+        year: query.year,
+        count: values.len(),
+        peer_count: filter.map(|_| peer_values.len()),
+        stale: ranked.iter().any(|(_, data)| data.stale),
+        rankings,
+    })
+}
+
+fn latest(data: &CountryData) -> f64 {
+    data.data.last().map_or(f64::NAN, |point| point.value)
+}
+
+/// Rank and percentile of `value` among `sorted`, which is in descending
+/// order.
+fn position(sorted: &[f64], value: f64) -> (usize, f64) {
+    let rank = sorted.partition_point(|v| *v > value) + 1;
+    let below = sorted.len() - sorted.partition_point(|v| *v >= value);
+    let percentile = if sorted.len() > 1 {
+        below as f64 / (sorted.len() - 1) as f64 * 100.0
+    } else {
+        100.0
+    };
+    (rank, percentile)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ties_share_the_best_rank() {
+        let sorted = [9.0, 5.0, 5.0, 1.0];
+        assert_eq!(position(&sorted, 9.0), (1, 100.0));
+        assert_eq!(position(&sorted, 5.0).0, 2);
+        assert_eq!(position(&sorted, 1.0), (4, 0.0));
+    }
+
+    #[test]
+    fn percentile_counts_only_strictly_lower_values() {
+        let sorted = [9.0, 5.0, 5.0, 1.0];
+        let (_, percentile) = position(&sorted, 5.0);
+        assert!((percentile - 100.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn single_economy_is_top() {
+        assert_eq!(position(&[3.0], 3.0), (1, 100.0));
+    }
+}
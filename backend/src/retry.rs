@@ -0,0 +1,48 @@
+use crate::config::UpstreamConfig;
+use crate::errors::ApiError;
+use rand::Rng;
+use std::time::Duration;
+
+/// Bounded retry schedule for idempotent upstream GETs, using exponential
+/// backoff with full jitter.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn from_config(upstream: &UpstreamConfig) -> Self {
+        RetryPolicy {
+            max_retries: upstream.max_retries,
+            base_delay: Duration::from_millis(upstream.retry_base_delay_ms),
+            max_delay: Duration::from_millis(upstream.retry_max_delay_ms),
+        }
+    }
+
+    /// Delay before retry number `attempt` (starting at 0): a random duration
+    /// between zero and `base_delay * 2^attempt`, capped at `max_delay`.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let ceiling = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+        let millis = ceiling.as_millis() as u64;
+        if millis == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::thread_rng().gen_range(0..=millis))
+    }
+
+    /// Timeouts, connection failures, throttling and 5xx responses are worth
+    /// another attempt; anything else will fail the same way again.
+    pub fn is_retryable(err: &ApiError) -> bool {
+        match err {
+            ApiError::UpstreamTimeout => true,
+            ApiError::UpstreamStatus(status) => *status == 429 || *status >= 500,
+            ApiError::UpstreamConnection(_) => true,
+            _ => false,
+        }
+    }
+}
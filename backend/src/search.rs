@@ -0,0 +1,199 @@
+use crate::models::Indicator;
+use crate::text::{edit_distance, max_typos};
+use std::collections::{BTreeMap, HashMap};
+
+/// Field weights: a hit on the id or name outranks one buried in the notes.
+const ID_WEIGHT: u32 = 4;
+const NAME_WEIGHT: u32 = 3;
+const TOPIC_WEIGHT: u32 = 2;
+const UNIT_WEIGHT: u32 = 2;
+const NOTE_WEIGHT: u32 = 1;
+
+/// Bonus for a query that is the indicator id itself.
+const EXACT_ID_BONUS: u32 = 100;
+
+/// Shortest query term that may match with typos; shorter terms would match
+/// nearly anything.
+const MIN_FUZZY_LEN: usize = 4;
+
+/// Inverted index over the indicator catalog. Each term maps to the
+/// indicators containing it, weighted by the best field it appears in.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    terms: BTreeMap<String, Vec<(usize, u32)>>,
+    /// Terms bucketed by length in characters, so typo candidates are only
+    /// the terms whose length is within the budget.
+    by_length: Vec<Vec<String>>,
+}
+
+impl SearchIndex {
+    pub fn build(indicators: &[Indicator]) -> Self {
+        let mut weights: HashMap<(String, usize), u32> = HashMap::new();
+        for (doc, indicator) in indicators.iter().enumerate() {
+            let fields = [
+                (indicator.id.as_str(), ID_WEIGHT),
+                (indicator.name.as_str(), NAME_WEIGHT),
+                (indicator.unit.as_str(), UNIT_WEIGHT),
+                (indicator.source_note.as_str(), NOTE_WEIGHT),
+            ]
+            .into_iter()
+            .chain(indicator.topics.iter().map(|t| (t.as_str(), TOPIC_WEIGHT)));
+
+            for (text, weight) in fields {
+                for term in tokenize(text) {
+                    let best = weights.entry((term, doc)).or_insert(0);
+                    *best = (*best).max(weight);
+                }
+            }
+        }
+
+        let mut terms: BTreeMap<String, Vec<(usize, u32)>> = BTreeMap::new();
+        for ((term, doc), weight) in weights {
+            terms.entry(term).or_default().push((doc, weight));
+        }
+
+        let mut by_length: Vec<Vec<String>> = Vec::new();
+        for term in terms.keys() {
+            let len = term.chars().count();
+            if by_length.len() <= len {
+                by_length.resize_with(len + 1, Vec::new);
+            }
+            by_length[len].push(term.clone());
+        }
+        SearchIndex { terms, by_length }
+    }
+
+    /// Returns `(position, score)` pairs for indicators matching every term
+    /// of `query`, best first. Terms match exactly, as a prefix, or within
+    /// the typo budget, in decreasing order of score.
+    pub fn search(&self, indicators: &[Indicator], query: &str, limit: usize) -> Vec<(usize, u32)> {
+        let mut totals: Option<HashMap<usize, u32>> = None;
+        for token in tokenize(query) {
+            let scores = self.score_term(&token);
+            totals = Some(match totals {
+                None => scores,
+                Some(totals) => totals
+                    .into_iter()
+                    .filter_map(|(doc, total)| scores.get(&doc).map(|score| (doc, total + score)))
+                    .collect(),
+            });
+        }
+
+        let query = query.trim();
+        let mut ranked: Vec<(usize, u32)> = totals
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(doc, score)| {
+                if indicators[doc].id.eq_ignore_ascii_case(query) {
+                    (doc, score + EXACT_ID_BONUS)
+                } else {
+                    (doc, score)
+                }
+            })
+            .collect();
+
+        ranked.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| indicators[a.0].id.cmp(&indicators[b.0].id))
+        });
+        ranked.truncate(limit);
+        ranked
+    }
+
+    fn score_term(&self, token: &str) -> HashMap<usize, u32> {
+        let mut scores: HashMap<usize, u32> = HashMap::new();
+        let mut add = |postings: &[(usize, u32)], quality: u32| {
+            for &(doc, weight) in postings {
+                let best = scores.entry(doc).or_insert(0);
+                *best = (*best).max(quality * weight);
+            }
+        };
+
+        let matches = self
+            .terms
+            .range(token.to_string()..)
+            .take_while(|(term, _)| term.starts_with(token));
+        for (term, postings) in matches {
+            add(postings, if term == token { 3 } else { 2 });
+        }
+
+        let len = token.chars().count();
+        if len >= MIN_FUZZY_LEN {
+            let budget = max_typos(len);
+            let candidates = self
+                .by_length
+                .iter()
+                .take(len + budget + 1)
+                .skip(len - budget)
+                .flatten();
+            for term in candidates {
+                if !term.starts_with(token) && edit_distance(term, token) <= budget {
+                    add(&self.terms[term], 1);
+                }
+            }
+        }
+        scores
+    }
+}
+
+/// Lowercased alphanumeric runs, so `NY.GDP.MKTP.CD` indexes as
+/// `ny`, `gdp`, `mktp` and `cd`.
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog(names: &[&str]) -> Vec<Indicator> {
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Indicator {
+                id: format!("TEST.{}", i),
+                name: name.to_string(),
+                ..Indicator::default()
+            })
+            .collect()
+    }
+
+    fn names(indicators: &[Indicator], query: &str) -> Vec<String> {
+        SearchIndex::build(indicators)
+            .search(indicators, query, 10)
+            .into_iter()
+            .map(|(doc, _)| indicators[doc].name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn exact_matches_outrank_prefixes_and_typos() {
+        let indicators = catalog(&[
+            "Grade attainment",
+            "Trademark applications",
+            "Trade balance",
+        ]);
+        assert_eq!(
+            names(&indicators, "trade"),
+            [
+                "Trade balance",
+                "Trademark applications",
+                "Grade attainment"
+            ]
+        );
+    }
+
+    #[test]
+    fn typos_are_limited_by_term_length() {
+        let indicators = catalog(&["Expert panels", "Import duties", "Support ratio"]);
+        assert_eq!(
+            names(&indicators, "export"),
+            ["Expert panels", "Import duties"]
+        );
+
+        let indicators = catalog(&["Tab count"]);
+        assert!(names(&indicators, "tax").is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+use crate::cache::CacheStats;
+use crate::circuit_breaker::CircuitStatus;
+use crate::errors::ApiError;
+use crate::models::{CountryData, Geography, Indicator, IndicatorCatalog, IndicatorCategory};
+use crate::query::SeriesQuery;
+use crate::validation::find_indicator;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Outcome of a batch fetch for one country.
+pub type BatchEntry = (String, Result<CountryData, ApiError>);
+
+/// A provider of countries, indicators and indicator series. Handlers are
+/// generic over this trait so the World Bank client can be swapped for other
+/// providers or an in-process fake.
+#[async_trait]
+pub trait DataSource: Send + Sync {
+    /// Countries and aggregates.
+    async fn geography(&self) -> Result<Geography, ApiError>;
+
+    /// The curated indicators shown by default.
+    async fn list_indicators(&self) -> Result<IndicatorCategory, ApiError>;
+
+    /// Every indicator that may be requested. Defaults to the curated list.
+    async fn indicator_catalog(&self) -> Result<Arc<IndicatorCatalog>, ApiError> {
+        Ok(Arc::new(IndicatorCatalog::featured_only(
+            self.list_indicators().await?,
+        )))
+    }
+
+    /// One indicator with its full metadata. Defaults to the catalog entry.
+    async fn indicator_metadata(&self, indicator_id: &str) -> Result<Indicator, ApiError> {
+        let catalog = self.indicator_catalog().await?;
+        find_indicator(catalog.iter(), indicator_id).cloned()
+    }
+
+    async fn fetch_series(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Result<CountryData, ApiError>;
+
+    /// Fetches one indicator for several countries, returning an outcome per
+    /// country in request order. Sources that can answer in a single round
+    /// trip should override this; the default fetches each series in turn.
+    async fn fetch_series_batch(
+        &self,
+        country_ids: &[String],
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Vec<BatchEntry> {
+        let mut results = Vec::with_capacity(country_ids.len());
+        for country_id in country_ids {
+            let result = self.fetch_series(country_id, indicator_id, query).await;
+            results.push((country_id.clone(), result));
+        }
+        results
+    }
+
+    /// One indicator for every economy, narrowed by `query`. Economies without
+    /// observations are left out. Sources that can answer with a single
+    /// `country/all` request should override this; the default goes through
+    /// `fetch_series_batch`.
+    async fn fetch_cross_section(
+        &self,
+        indicator_id: &str,
+        query: &SeriesQuery,
+    ) -> Result<Vec<CountryData>, ApiError> {
+        let geography = self.geography().await?;
+        let country_ids: Vec<String> = geography.countries.into_iter().map(|c| c.id).collect();
+
+        let mut series = Vec::new();
+        let mut failure = None;
+        for (_, result) in self
+            .fetch_series_batch(&country_ids, indicator_id, query)
+            .await
+        {
+            match result {
+                Ok(data) => series.push(data),
+                Err(ApiError::NoDataAvailable) => {}
+                Err(e) => {
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+        match failure {
+            Some(e) if series.is_empty() => Err(e),
+            _ => Ok(series),
+        }
+    }
+
+    fn cache_stats(&self) -> Option<CacheStats> {
+        None
+    }
+
+    fn circuit_status(&self) -> Option<CircuitStatus> {
+        None
+    }
+}
@@ -0,0 +1,159 @@
+use crate::models::DataPoint;
+use crate::query::point_year;
+use serde::{Deserialize, Serialize};
+
+/// The `stats=true` query parameter, which adds a summary to each series.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct StatsQuery {
+    #[serde(default)]
+    pub stats: bool,
+}
+
+/// Summary statistics of a series. `min`, `max` and `latest` carry the period
+/// they occurred in; ties go to the earliest period.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesStats {
+    pub count: usize,
+    pub min: DataPoint,
+    pub max: DataPoint,
+    pub mean: f64,
+    pub median: f64,
+    /// Sample standard deviation; absent for a single observation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub std_dev: Option<f64>,
+    pub latest: DataPoint,
+    /// Least-squares slope, in units per year; absent unless the series spans
+    /// at least two years.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trend_slope: Option<f64>,
+}
+
+/// Response of `/api/stats/{country_id}/{indicator_id}`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SeriesSummary {
+    pub country_id: String,
+    pub country_name: String,
+    pub indicator_id: String,
+    pub indicator_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transform: Option<String>,
+    pub stats: SeriesStats,
+}
+
+/// Summarises a series ordered by period. Returns `None` for an empty series.
+pub fn summarize(points: &[DataPoint]) -> Option<SeriesStats> {
+    let latest = points.last()?.clone();
+    let count = points.len();
+    let n = count as f64;
+
+    let min = points.iter().fold(
+        &points[0],
+        |min, p| if p.value < min.value { p } else { min },
+    );
+    let max = points.iter().fold(
+        &points[0],
+        |max, p| if p.value > max.value { p } else { max },
+    );
+
+    let mean = points.iter().map(|p| p.value).sum::<f64>() / n;
+
+    let mut values: Vec<f64> = points.iter().map(|p| p.value).collect();
+    values.sort_by(f64::total_cmp);
+    let median = if count % 2 == 1 {
+        values[count / 2]
+    } else {
+        (values[count / 2 - 1] + values[count / 2]) / 2.0
+    };
+
+    let std_dev = (count > 1).then(|| {
+        let variance = points.iter().map(|p| (p.value - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        variance.sqrt()
+    });
+
+    Some(SeriesStats {
+        count,
+        min: min.clone(),
+        max: max.clone(),
+        mean,
+        median,
+        std_dev,
+        latest,
+        trend_slope: trend_slope(points),
+    })
+}
+
+fn trend_slope(points: &[DataPoint]) -> Option<f64> {
+    let xy: Vec<(f64, f64)> = points
+        .iter()
+        .filter_map(|p| Some((point_year(p)? as f64, p.value)))
+        .collect();
+    if xy.len() < 2 {
+        return None;
+    }
+    let n = xy.len() as f64;
+    let mean_x = xy.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = xy.iter().map(|(_, y)| y).sum::<f64>() / n;
+
+    let sxx: f64 = xy.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if sxx == 0.0 {
+        return None;
+    }
+    let sxy: f64 = xy.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    Some(sxy / sxx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CountryData;
+
+    fn points(values: &[(&str, f64)]) -> Vec<DataPoint> {
+        CountryData::test_series("USA", "TEST", values).data
+    }
+
+    #[test]
+    fn median_of_even_length_averages_the_middle_pair() {
+        let stats = summarize(&points(&[
+            ("2017", 4.0),
+            ("2018", 1.0),
+            ("2019", 3.0),
+            ("2020", 2.0),
+        ]))
+        .unwrap();
+        assert_eq!(stats.median, 2.5);
+        assert_eq!(stats.mean, 2.5);
+        assert_eq!(stats.count, 4);
+    }
+
+    #[test]
+    fn extremes_keep_the_earliest_period_on_ties() {
+        let stats = summarize(&points(&[
+            ("2018", 5.0),
+            ("2019", 1.0),
+            ("2020", 5.0),
+            ("2021", 1.0),
+        ]))
+        .unwrap();
+        assert_eq!(stats.max.year, "2018");
+        assert_eq!(stats.min.year, "2019");
+        assert_eq!(stats.latest.year, "2021");
+    }
+
+    #[test]
+    fn single_point_has_no_spread_or_trend() {
+        let stats = summarize(&points(&[("2020", 7.0)])).unwrap();
+        assert_eq!(stats.median, 7.0);
+        assert_eq!(stats.std_dev, None);
+        assert_eq!(stats.trend_slope, None);
+        assert!(summarize(&[]).is_none());
+    }
+
+    #[test]
+    fn trend_slope_is_per_year() {
+        let stats = summarize(&points(&[("2010", 1.0), ("2012", 5.0), ("2014", 9.0)])).unwrap();
+        assert_eq!(stats.trend_slope, Some(2.0));
+        assert_eq!(stats.std_dev, Some(4.0));
+    }
+}
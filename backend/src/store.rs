@@ -0,0 +1,169 @@
+use crate::errors::ApiError;
+use crate::models::{CountryData, Geography, IndicatorCatalog};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS countries (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        payload TEXT NOT NULL,
+        refreshed_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS catalog (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        payload TEXT NOT NULL,
+        refreshed_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS series (
+        country_id TEXT NOT NULL,
+        indicator_id TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        refreshed_at TEXT NOT NULL,
+        PRIMARY KEY (country_id, indicator_id)
+    );
+";
+
+/// A value read back from the store together with the time it was last
+/// fetched from upstream.
+#[derive(Debug, Clone)]
+pub struct Stored<T> {
+    pub value: T,
+    pub refreshed_at: DateTime<Utc>,
+}
+
+/// Persistent SQLite store for the countries and aggregates, indicator catalog
+/// and indicator series.
+/// Payloads are kept as JSON so the schema does not need to track the models.
+#[derive(Clone)]
+pub struct Store {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, ApiError> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Store {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    pub async fn load_geography(&self) -> Result<Option<Stored<Geography>>, ApiError> {
+        self.with_conn(|conn| {
+            let row = conn
+                .query_row(
+                    "SELECT payload, refreshed_at FROM countries WHERE id = 1",
+                    [],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()?;
+            row.map(|(payload, refreshed_at)| decode(&payload, &refreshed_at))
+                .transpose()
+        })
+        .await
+    }
+
+    pub async fn save_geography(&self, geography: &Geography) -> Result<(), ApiError> {
+        let payload = serde_json::to_string(geography)?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO countries (id, payload, refreshed_at) VALUES (1, ?1, ?2)",
+                params![payload, Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn load_catalog(&self) -> Result<Option<Stored<IndicatorCatalog>>, ApiError> {
+        self.with_conn(|conn| {
+            let row = conn
+                .query_row(
+                    "SELECT payload, refreshed_at FROM catalog WHERE id = 1",
+                    [],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()?;
+            row.map(|(payload, refreshed_at)| decode(&payload, &refreshed_at))
+                .transpose()
+        })
+        .await
+    }
+
+    pub async fn save_catalog(&self, catalog: &IndicatorCatalog) -> Result<(), ApiError> {
+        let payload = serde_json::to_string(catalog)?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO catalog (id, payload, refreshed_at) VALUES (1, ?1, ?2)",
+                params![payload, Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    pub async fn load_series(
+        &self,
+        country_id: &str,
+        indicator_id: &str,
+    ) -> Result<Option<Stored<CountryData>>, ApiError> {
+        let country_id = country_id.to_uppercase();
+        let indicator_id = indicator_id.to_uppercase();
+        self.with_conn(move |conn| {
+            let row = conn
+                .query_row(
+                    "SELECT payload, refreshed_at FROM series
+                     WHERE country_id = ?1 AND indicator_id = ?2",
+                    params![country_id, indicator_id],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+                )
+                .optional()?;
+            row.map(|(payload, refreshed_at)| decode(&payload, &refreshed_at))
+                .transpose()
+        })
+        .await
+    }
+
+    pub async fn save_series(&self, data: &CountryData) -> Result<(), ApiError> {
+        let country_id = data.country_id.to_uppercase();
+        let indicator_id = data.indicator_id.to_uppercase();
+        let payload = serde_json::to_string(data)?;
+        self.with_conn(move |conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO series (country_id, indicator_id, payload, refreshed_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![country_id, indicator_id, payload, Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn with_conn<T, F>(&self, f: F) -> Result<T, ApiError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, ApiError> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let conn = conn.lock().map_err(|_| ApiError::InternalError)?;
+            f(&conn)
+        })
+        .await
+        .map_err(|_| ApiError::InternalError)?
+    }
+}
+
+fn decode<T: DeserializeOwned>(payload: &str, refreshed_at: &str) -> Result<Stored<T>, ApiError> {
+    let value = serde_json::from_str(payload)?;
+    let refreshed_at = DateTime::parse_from_rfc3339(refreshed_at)
+        .map_err(|e| ApiError::StorageError(e.to_string()))?
+        .with_timezone(&Utc);
+    Ok(Stored {
+        value,
+        refreshed_at,
+    })
+}
@@ -0,0 +1,46 @@
+/// Levenshtein distance between two strings, counted in characters.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// How many edits a query of this length may contain and still count as a
+/// near match.
+pub fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 1,
+        4..=7 => 2,
+        _ => 3,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_characters() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "usa"), 3);
+        assert_eq!(edit_distance("côte", "cote"), 1);
+    }
+
+    #[test]
+    fn longer_queries_allow_more_typos() {
+        assert_eq!(max_typos(3), 1);
+        assert_eq!(max_typos(7), 2);
+        assert_eq!(max_typos(8), 3);
+    }
+}
@@ -0,0 +1,379 @@
+use crate::errors::ApiError;
+use crate::models::{CountryData, DataPoint};
+use crate::query::point_year;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// The `transform=` query parameter shared by the data endpoints.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TransformQuery {
+    pub transform: Option<String>,
+}
+
+impl TransformQuery {
+    pub fn pipeline(&self) -> Result<Pipeline, ApiError> {
+        match &self.transform {
+            Some(spec) => spec.parse(),
+            None => Ok(Pipeline::default()),
+        }
+    }
+}
+
+/// One step of a transform pipeline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    /// `yoy`: percent change from the same period a year earlier.
+    YearOverYear,
+    /// `cagr` or `cagr:FROM:TO`: compound annual growth rate in percent
+    /// between two years, or the first and last observations.
+    Cagr { from: Option<i32>, to: Option<i32> },
+    /// `index` or `index:YEAR`: rebased so the base year, or the first
+    /// observation, is 100.
+    Index { base: Option<i32> },
+    /// `ma:N`: trailing average of the last N observations.
+    MovingAverage { window: usize },
+}
+
+impl FromStr for Transform {
+    type Err = ApiError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut parts = spec.trim().split(':');
+        let name = parts.next().unwrap_or_default().to_lowercase();
+        let args: Vec<&str> = parts.collect();
+
+        let transform = match (name.as_str(), args.as_slice()) {
+            ("yoy", []) => Transform::YearOverYear,
+            ("cagr", []) => Transform::Cagr {
+                from: None,
+                to: None,
+            },
+            ("cagr", [from, to]) => {
+                let (from, to) = (parse_year(from)?, parse_year(to)?);
+                if from >= to {
+                    return Err(ApiError::InvalidQuery(format!(
+                        "cagr start year {} must be before end year {}",
+                        from, to
+                    )));
+                }
+                Transform::Cagr {
+                    from: Some(from),
+                    to: Some(to),
+                }
+            }
+            ("index", []) => Transform::Index { base: None },
+            ("index", [base]) => Transform::Index {
+                base: Some(parse_year(base)?),
+            },
+            ("ma", [window]) => match window.parse() {
+                Ok(window) if window > 0 => Transform::MovingAverage { window },
+                _ => {
+                    return Err(ApiError::InvalidQuery(format!(
+                        "ma window must be a positive integer, got {}",
+                        window
+                    )))
+                }
+            },
+            _ => {
+                return Err(ApiError::InvalidQuery(format!(
+                    "unknown transform {}; expected yoy, cagr[:FROM:TO], index[:YEAR] or ma:N",
+                    spec
+                )))
+            }
+        };
+        Ok(transform)
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Transform::YearOverYear => write!(f, "yoy"),
+            Transform::Cagr {
+                from: Some(from),
+                to: Some(to),
+            } => write!(f, "cagr:{}:{}", from, to),
+            Transform::Cagr { .. } => write!(f, "cagr"),
+            Transform::Index { base: Some(base) } => write!(f, "index:{}", base),
+            Transform::Index { base: None } => write!(f, "index"),
+            Transform::MovingAverage { window } => write!(f, "ma:{}", window),
+        }
+    }
+}
+
+impl Transform {
+    pub fn apply(&self, points: Vec<DataPoint>) -> Result<Vec<DataPoint>, ApiError> {
+        match *self {
+            Transform::YearOverYear => Ok(year_over_year(&points)),
+            Transform::Cagr { from, to } => cagr(&points, from, to),
+            Transform::Index { base } => index(points, base),
+            Transform::MovingAverage { window } => Ok(moving_average(&points, window)),
+        }
+    }
+}
+
+/// Transforms applied left to right, written as a comma separated list such
+/// as `ma:3,yoy`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pipeline(Vec<Transform>);
+
+impl FromStr for Pipeline {
+    type Err = ApiError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        spec.split(',')
+            .filter(|step| !step.trim().is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map(Pipeline)
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<String> = self.0.iter().map(Transform::to_string).collect();
+        write!(f, "{}", steps.join(","))
+    }
+}
+
+impl Pipeline {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn apply_points(&self, points: Vec<DataPoint>) -> Result<Vec<DataPoint>, ApiError> {
+        let points = self
+            .0
+            .iter()
+            .try_fold(points, |points, transform| transform.apply(points))?;
+        if points.is_empty() {
+            return Err(ApiError::TransformFailed(format!(
+                "{} left no data points",
+                self
+            )));
+        }
+        Ok(points)
+    }
+
+    /// Applies the pipeline to a series and records it in `transform`.
+    pub fn apply(&self, mut data: CountryData) -> Result<CountryData, ApiError> {
+        if self.is_empty() {
+            return Ok(data);
+        }
+        data.data = self.apply_points(data.data)?;
+        data.transform = Some(self.to_string());
+        Ok(data)
+    }
+}
+
+fn parse_year(year: &str) -> Result<i32, ApiError> {
+    year.parse()
+        .map_err(|_| ApiError::InvalidQuery(format!("expected a year, got {}", year)))
+}
+
+/// Sub-annual dates keep their period, so `2020Q1` is compared with `2019Q1`.
+fn year_over_year(points: &[DataPoint]) -> Vec<DataPoint> {
+    let by_date: HashMap<&str, f64> = points
+        .iter()
+        .map(|point| (point.year.as_str(), point.value))
+        .collect();
+
+    points
+        .iter()
+        .filter_map(|point| {
+            let year = point_year(point)?;
+            let previous = format!("{}{}", year - 1, &point.year[4..]);
+            let previous = *by_date.get(previous.as_str())?;
+            if previous == 0.0 {
+                return None;
+            }
+            Some(DataPoint {
+                year: point.year.clone(),
+                value: (point.value / previous - 1.0) * 100.0,
+            })
+        })
+        .collect()
+}
+
+fn find_year(points: &[DataPoint], year: i32) -> Option<&DataPoint> {
+    points.iter().find(|point| point_year(point) == Some(year))
+}
+
+/// A single point labelled `FROM-TO`.
+fn cagr(
+    points: &[DataPoint],
+    from: Option<i32>,
+    to: Option<i32>,
+) -> Result<Vec<DataPoint>, ApiError> {
+    let start = from.map_or(points.first(), |year| find_year(points, year));
+    let end = to.map_or(points.last(), |year| find_year(points, year));
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) => (start, end),
+        _ => {
+            return Err(ApiError::TransformFailed(
+                "cagr needs observations for both years".to_string(),
+            ))
+        }
+    };
+
+    let years = match (point_year(start), point_year(end)) {
+        (Some(start), Some(end)) if end > start => end - start,
+        _ => {
+            return Err(ApiError::TransformFailed(
+                "cagr needs observations in two different years".to_string(),
+            ))
+        }
+    };
+    if start.value <= 0.0 || end.value <= 0.0 {
+        return Err(ApiError::TransformFailed(
+            "cagr is only defined for positive values".to_string(),
+        ));
+    }
+
+    Ok(vec![DataPoint {
+        year: format!("{}-{}", start.year, end.year),
+        value: ((end.value / start.value).powf(1.0 / years as f64) - 1.0) * 100.0,
+    }])
+}
+
+fn index(points: Vec<DataPoint>, base: Option<i32>) -> Result<Vec<DataPoint>, ApiError> {
+    let base_value = base
+        .map_or(points.first(), |year| find_year(&points, year))
+        .map(|point| point.value);
+
+    match base_value {
+        Some(base_value) if base_value != 0.0 => Ok(points
+            .into_iter()
+            .map(|point| DataPoint {
+                value: point.value / base_value * 100.0,
+                ..point
+            })
+            .collect()),
+        Some(_) => Err(ApiError::TransformFailed(
+            "cannot index to a base value of 0".to_string(),
+        )),
+        None => Err(ApiError::TransformFailed(match base {
+            Some(base) => format!("no observation for base year {}", base),
+            None => "no observations to index".to_string(),
+        })),
+    }
+}
+
+fn moving_average(points: &[DataPoint], window: usize) -> Vec<DataPoint> {
+    points
+        .windows(window)
+        .map(|slice| DataPoint {
+            year: slice[slice.len() - 1].year.clone(),
+            value: slice.iter().map(|p| p.value).sum::<f64>() / window as f64,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(values: &[(&str, f64)]) -> Vec<DataPoint> {
+        CountryData::test_series("USA", "TEST", values).data
+    }
+
+    fn approx(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn apply(spec: &str, values: &[(&str, f64)]) -> Result<Vec<DataPoint>, ApiError> {
+        spec.parse::<Pipeline>()?.apply_points(points(values))
+    }
+
+    #[test]
+    fn yoy_compares_the_same_quarter() {
+        let result = apply(
+            "yoy",
+            &[
+                ("2019Q1", 100.0),
+                ("2019Q2", 200.0),
+                ("2020Q1", 110.0),
+                ("2020Q2", 180.0),
+            ],
+        )
+        .unwrap();
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].year, "2020Q1");
+        approx(result[0].value, 10.0);
+        assert_eq!(result[1].year, "2020Q2");
+        approx(result[1].value, -10.0);
+    }
+
+    #[test]
+    fn cagr_spans_sub_annual_periods_by_year() {
+        let result = apply(
+            "cagr",
+            &[("2018Q4", 100.0), ("2019Q4", 105.0), ("2020Q4", 121.0)],
+        )
+        .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].year, "2018Q4-2020Q4");
+        approx(result[0].value, 10.0);
+    }
+
+    #[test]
+    fn cagr_needs_two_different_years() {
+        assert!(matches!(
+            apply("cagr", &[("2020Q1", 100.0), ("2020Q4", 110.0)]),
+            Err(ApiError::TransformFailed(_))
+        ));
+        assert!(matches!(
+            "cagr:2020:2010".parse::<Pipeline>(),
+            Err(ApiError::InvalidQuery(_))
+        ));
+    }
+
+    #[test]
+    fn index_uses_the_first_period_of_the_base_year() {
+        let result = apply(
+            "index:2020",
+            &[("2019Q4", 40.0), ("2020Q1", 50.0), ("2020Q2", 75.0)],
+        )
+        .unwrap();
+        let values: Vec<f64> = result.iter().map(|p| p.value).collect();
+        assert_eq!(values, [80.0, 100.0, 150.0]);
+    }
+
+    #[test]
+    fn index_fails_without_the_base_year() {
+        assert!(matches!(
+            apply("index:2015", &[("2020", 1.0)]),
+            Err(ApiError::TransformFailed(_))
+        ));
+    }
+
+    #[test]
+    fn pipeline_applies_steps_left_to_right() {
+        let pipeline: Pipeline = "ma:2, yoy".parse().unwrap();
+        assert_eq!(pipeline.to_string(), "ma:2,yoy");
+        let result = pipeline
+            .apply_points(points(&[("2018", 10.0), ("2019", 30.0), ("2020", 50.0)]))
+            .unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].year, "2020");
+        approx(result[0].value, 100.0);
+    }
+
+    #[test]
+    fn rejects_unknown_steps() {
+        for spec in ["growth", "ma:0", "ma", "index:abc"] {
+            assert!(
+                matches!(spec.parse::<Pipeline>(), Err(ApiError::InvalidQuery(_))),
+                "{:?} should be rejected",
+                spec
+            );
+        }
+    }
+}
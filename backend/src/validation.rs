@@ -0,0 +1,171 @@
+use crate::errors::ApiError;
+use crate::models::{Aggregate, Country, Geography, Indicator, IndicatorCatalog};
+use crate::text::{edit_distance, max_typos};
+use std::borrow::Cow;
+
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Looks up a country by ISO3 id or ISO2 code, ignoring case.
+pub fn find_country<'a>(countries: &'a [Country], id: &str) -> Result<&'a Country, ApiError> {
+    countries
+        .iter()
+        .find(|c| c.id.eq_ignore_ascii_case(id) || c.iso2_code.eq_ignore_ascii_case(id))
+        .ok_or_else(|| ApiError::CountryNotFound {
+            id: id.to_string(),
+            suggestions: suggest(
+                id,
+                countries
+                    .iter()
+                    .map(|c| (c.id.as_str(), vec![c.id.as_str(), c.iso2_code.as_str(), &c.name])),
+            ),
+        })
+}
+
+/// A country or aggregate named in a request.
+#[derive(Debug, Clone, Copy)]
+pub enum Area<'a> {
+    Country(&'a Country),
+    Aggregate(&'a Aggregate),
+}
+
+impl<'a> Area<'a> {
+    pub fn id(&self) -> &'a str {
+        match self {
+            Area::Country(country) => &country.id,
+            Area::Aggregate(aggregate) => &aggregate.id,
+        }
+    }
+}
+
+/// Looks up a country, or failing that an aggregate, by ISO3 id or ISO2 code,
+/// ignoring case.
+pub fn find_area<'a>(geography: &'a Geography, id: &str) -> Result<Area<'a>, ApiError> {
+    if let Some(country) = geography
+        .countries
+        .iter()
+        .find(|c| c.id.eq_ignore_ascii_case(id) || c.iso2_code.eq_ignore_ascii_case(id))
+    {
+        return Ok(Area::Country(country));
+    }
+
+    geography
+        .aggregates
+        .iter()
+        .find(|a| a.id.eq_ignore_ascii_case(id) || a.iso2_code.eq_ignore_ascii_case(id))
+        .map(Area::Aggregate)
+        .ok_or_else(|| ApiError::CountryNotFound {
+            id: id.to_string(),
+            suggestions: suggest(
+                id,
+                geography
+                    .countries
+                    .iter()
+                    .map(|c| (c.id.as_str(), vec![c.id.as_str(), c.iso2_code.as_str(), &c.name]))
+                    .chain(geography.aggregates.iter().map(|a| {
+                        (a.id.as_str(), vec![a.id.as_str(), a.iso2_code.as_str(), &a.name])
+                    })),
+            ),
+        })
+}
+
+/// Looks up an indicator by id, ignoring case.
+pub fn find_indicator<'a, I>(indicators: I, id: &str) -> Result<&'a Indicator, ApiError>
+where
+    I: IntoIterator<Item = &'a Indicator>,
+    I::IntoIter: Clone,
+{
+    let indicators = indicators.into_iter();
+    indicators
+        .clone()
+        .find(|i| i.id.eq_ignore_ascii_case(id))
+        .ok_or_else(|| ApiError::IndicatorNotFound {
+            id: id.to_string(),
+            suggestions: suggest(
+                id,
+                indicators.map(|i| (i.id.as_str(), vec![i.id.as_str(), &i.name])),
+            ),
+        })
+}
+
+/// Looks up an indicator in the catalog. While the catalog is partial, an
+/// unknown id is passed through as given so upstream can decide on it.
+pub fn resolve_indicator<'a>(
+    catalog: &'a IndicatorCatalog,
+    id: &str,
+) -> Result<Cow<'a, Indicator>, ApiError> {
+    match find_indicator(catalog.iter(), id) {
+        Ok(indicator) => Ok(Cow::Borrowed(indicator)),
+        Err(_) if catalog.partial => {
+            let id = id.trim().to_uppercase();
+            Ok(Cow::Owned(Indicator {
+                name: id.clone(),
+                id,
+                ..Indicator::default()
+            }))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Ranks candidates by their closest edit distance to `query` over any of
+/// their names, keeping those within the typo budget. Names containing the
+/// query outright always qualify.
+fn suggest<'a, C>(query: &str, candidates: C) -> Vec<String>
+where
+    C: Iterator<Item = (&'a str, Vec<&'a str>)>,
+{
+    let query = query.to_lowercase();
+    let budget = max_typos(query.chars().count());
+
+    let mut scored: Vec<(usize, &str)> = candidates
+        .filter_map(|(id, names)| {
+            names
+                .iter()
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    let name = name.to_lowercase();
+                    if query.len() >= 3 && name.contains(&query) {
+                        0
+                    } else {
+                        edit_distance(&query, &name)
+                    }
+                })
+                .min()
+                .filter(|distance| *distance <= budget)
+                .map(|distance| (distance, id))
+        })
+        .collect();
+
+    scored.sort();
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, id)| id.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data;
+
+    #[test]
+    fn partial_catalog_passes_unknown_indicators_through() {
+        let catalog = IndicatorCatalog::featured_only(data::get_indicators());
+        let indicator = resolve_indicator(&catalog, "en.atm.co2e.kt").unwrap();
+        assert_eq!(indicator.id, "EN.ATM.CO2E.KT");
+    }
+
+    #[test]
+    fn full_catalog_rejects_unknown_indicators() {
+        let catalog = IndicatorCatalog {
+            partial: false,
+            ..IndicatorCatalog::featured_only(data::get_indicators())
+        };
+        assert!(matches!(
+            resolve_indicator(&catalog, "EN.ATM.CO2E.KT"),
+            Err(ApiError::IndicatorNotFound { .. })
+        ));
+        assert!(resolve_indicator(&catalog, "ny.gdp.mktp.cd").is_ok());
+    }
+}
@@ -0,0 +1,209 @@
+use crate::errors::ApiError;
+use serde::de::{self, DeserializeOwned, Deserializer};
+use serde::Deserialize;
+use serde_json::Value;
+
+/// World Bank error id for a parameter value it does not recognise, e.g. an
+/// unknown country code.
+pub const INVALID_VALUE: &str = "120";
+
+/// Region id the World Bank files aggregates such as `WLD` under.
+pub const AGGREGATE_REGION: &str = "NA";
+
+/// World Bank error id returned for an unknown or archived indicator.
+pub const INDICATOR_NOT_FOUND: &str = "175";
+
+/// Header object sent as element 0 of every paged response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PageMeta {
+    #[serde(deserialize_with = "lenient_u64")]
+    pub pages: u64,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub total: u64,
+}
+
+/// A page of records, with the header already split off.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub meta: PageMeta,
+    pub records: Vec<T>,
+}
+
+/// The error form `[{"message": [{"id": ..., "key": ..., "value": ...}]}]`.
+#[derive(Debug, Clone, Deserialize)]
+struct ErrorBody {
+    message: Vec<ErrorMessage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ErrorMessage {
+    id: String,
+    #[serde(default)]
+    key: String,
+    #[serde(default)]
+    value: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Envelope {
+    Page(PageMeta, Option<Vec<Value>>),
+    Error(Vec<ErrorBody>),
+}
+
+/// The `{"id": ..., "value": ...}` objects used for regions, countries and
+/// indicators.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Reference {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CountryRecord {
+    pub id: String,
+    pub iso2_code: String,
+    pub name: String,
+    pub region: Reference,
+    /// Empty for high-income countries, which have no administrative region.
+    #[serde(default, rename = "adminregion")]
+    pub admin_region: Option<Reference>,
+    pub income_level: Reference,
+    pub lending_type: Reference,
+    pub capital_city: String,
+    /// Coordinates of the capital, as decimal strings; empty for aggregates.
+    #[serde(default)]
+    pub longitude: String,
+    #[serde(default)]
+    pub latitude: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndicatorRecord {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub unit: String,
+    /// The database the indicator belongs to, e.g. World Development Indicators.
+    pub source: Option<Reference>,
+    #[serde(default)]
+    pub source_note: String,
+    #[serde(default)]
+    pub source_organization: String,
+    /// May contain empty `{}` entries, which decode with an empty id.
+    #[serde(default)]
+    pub topics: Vec<Reference>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ObservationRecord {
+    pub indicator: Reference,
+    pub country: Reference,
+    /// ISO3 code of the country; empty for some aggregates, whose
+    /// `country.id` is used instead.
+    #[serde(default)]
+    pub countryiso3code: String,
+    pub date: String,
+    pub value: Option<f64>,
+}
+
+/// One `{"id": "Periodicity", "value": "Annual"}` entry of the series
+/// metadata endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Metatype {
+    pub id: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+/// The `/sources/{source}/series/{id}/metadata` response, which is an object
+/// rather than the usual `[meta, records]` pair.
+#[derive(Debug, Clone, Deserialize)]
+struct MetadataResponse {
+    #[serde(default)]
+    source: Vec<MetadataSource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MetadataSource {
+    #[serde(default)]
+    concept: Vec<MetadataConcept>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MetadataConcept {
+    #[serde(default)]
+    variable: Vec<MetadataVariable>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MetadataVariable {
+    #[serde(default)]
+    metatype: Vec<Metatype>,
+}
+
+/// Flattens a series metadata response into its metatype entries. Errors in
+/// the usual array form are turned into `ApiError::UpstreamRejected`.
+pub fn decode_metadata(data: &Value) -> Result<Vec<Metatype>, ApiError> {
+    if data.is_array() {
+        decode_page::<Value>(data)?;
+        return Ok(Vec::new());
+    }
+
+    let response = MetadataResponse::deserialize(data)?;
+    Ok(response
+        .source
+        .into_iter()
+        .flat_map(|source| source.concept)
+        .flat_map(|concept| concept.variable)
+        .flat_map(|variable| variable.metatype)
+        .collect())
+}
+
+/// Splits a World Bank response into its header and typed records. The error
+/// form is turned into `ApiError::UpstreamRejected`.
+pub fn decode_page<T: DeserializeOwned>(data: &Value) -> Result<Page<T>, ApiError> {
+    match Envelope::deserialize(data)? {
+        Envelope::Page(meta, records) => {
+            let records = records
+                .unwrap_or_default()
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<Result<Vec<T>, _>>()?;
+            Ok(Page { meta, records })
+        }
+        Envelope::Error(bodies) => {
+            let message = bodies
+                .into_iter()
+                .flat_map(|body| body.message)
+                .next()
+                .unwrap_or_else(|| ErrorMessage {
+                    id: String::new(),
+                    key: String::new(),
+                    value: "Unknown error".to_string(),
+                });
+            Err(ApiError::UpstreamRejected {
+                id: message.id,
+                message: if message.key.is_empty() {
+                    message.value
+                } else {
+                    format!("{}: {}", message.key, message.value)
+                },
+            })
+        }
+    }
+}
+
+fn lenient_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Number(n) => n
+            .as_u64()
+            .ok_or_else(|| de::Error::custom(format!("expected unsigned integer, got {}", n))),
+        Value::String(s) => s.parse().map_err(de::Error::custom),
+        other => Err(de::Error::custom(format!("expected number, got {}", other))),
+    }
+}
@@ -3,23 +3,42 @@ use gloo_net::error::FetchError;
 use crate::models::{Country, IndicatorCategory, CountryData, ComparisonRequest};
 use serde_json::json;
 
-const API_BASE: &str = "http://localhost:8080/api";
+const DEFAULT_API_BASE: &str = "http://localhost:8080/api";
+
+/// Resolves the backend base URL. A `<meta name="api-base" content="...">` tag
+/// in the host page wins at runtime, then the `BANK_API_BASE` variable set at
+/// build time, then the local development default.
+fn api_base() -> String {
+    let runtime = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.query_selector("meta[name='api-base']").ok().flatten())
+        .and_then(|meta| meta.get_attribute("content"))
+        .filter(|base| !base.is_empty());
+
+    runtime
+        .or_else(|| option_env!("BANK_API_BASE").map(str::to_string))
+        .unwrap_or_else(|| DEFAULT_API_BASE.to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
 
 pub struct ApiClient {
     client: Client,
+    base: String,
 }
 
 impl ApiClient {
     pub fn new() -> Self {
         ApiClient {
             client: Client::new(),
+            base: api_base(),
         }
     }
 
     pub async fn get_countries(&self) -> Result<Vec<Country>, FetchError> {
         let response = self
             .client
-            .get(&format!("{}/countries", API_BASE))
+            .get(&format!("{}/countries", self.base))
             .send()
             .await?;
         response.json().await
@@ -28,7 +47,7 @@ impl ApiClient {
     pub async fn get_indicators(&self) -> Result<IndicatorCategory, FetchError> {
         let response = self
             .client
-            .get(&format!("{}/indicators", API_BASE))
+            .get(&format!("{}/indicators", self.base))
             .send()
             .await?;
         response.json().await
@@ -37,7 +56,7 @@ impl ApiClient {
     pub async fn get_data(&self, country_id: &str, indicator_id: &str) -> Result<CountryData, FetchError> {
         let response = self
             .client
-            .get(&format!("{}/data/{}/{}", API_BASE, country_id, indicator_id))
+            .get(&format!("{}/data/{}/{}", self.base, country_id, indicator_id))
             .send()
             .await?;
         response.json().await
@@ -51,7 +70,7 @@ impl ApiClient {
         let request = ComparisonRequest { countries, indicators };
         let response = self
             .client
-            .post(&format!("{}/compare", API_BASE))
+            .post(&format!("{}/compare", self.base))
             .json(&request)
             .map_err(|_| FetchError::SerdeError)?
             .send()
@@ -19,7 +19,8 @@ pub fn country_selector(props: &CountrySelectorProps) -> Html {
             .filter(|c| {
                 search.is_empty()
                     || c.name.to_lowercase().contains(&search.to_lowercase())
-                    || c.code.to_lowercase().contains(&search.to_lowercase())
+                    || c.id.to_lowercase().contains(&search.to_lowercase())
+                    || c.iso2_code.to_lowercase().contains(&search.to_lowercase())
             })
             .cloned()
             .collect::<Vec<_>>()
@@ -6,7 +6,8 @@ pub struct Country {
     pub name: String,
     pub region: String,
     pub capital: String,
-    pub code: String,
+    #[serde(default)]
+    pub iso2_code: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]